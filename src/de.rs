@@ -5,73 +5,279 @@
 //! simplicity.
 //!
 //! Check [`DeserializeParams`] to get started.
+//!
+//! ## Implementing extras
+//!
+//! Deserialization of extras is split into two types: the extras themselves and their
+//! [`DeserializationState`]. The state is required to implement `Default` because a fresh state
+//! is created every time a URI is parsed, before any parameter is seen. The parameters are then
+//! fed to the state one by one and once all of them are processed the state is converted into the
+//! extras by [`finalize`](DeserializationState::finalize).
+//!
+//! Thanks to this split the extras may contain mandatory fields even though they can not have a
+//! sensible default value. The state stores them as `Option`s and `finalize` checks that they were
+//! found. (Alternatively [`require_fields`](DeserializationState::require_fields) can report them
+//! as [`Error::MissingRequiredExtras`].) [`EmptyState`](crate::EmptyState), the state of
+//! [`NoExtras`], is the simplest possible state.
+//!
+//! ```
+//! use bip21::de::{DeserializationError, DeserializationState, DeserializeParams, ParamKind};
+//! use bip21::Param;
+//! use core::convert::TryInto;
+//!
+//! /// Extras of a URI that must contain a Payjoin endpoint.
+//! pub struct Payjoin {
+//!     pub endpoint: String,
+//! }
+//!
+//! #[derive(Debug)]
+//! pub enum PayjoinError {
+//!     Utf8(core::str::Utf8Error),
+//!     MissingEndpoint,
+//! }
+//!
+//! impl DeserializationError for Payjoin {
+//!     type Error = PayjoinError;
+//! }
+//!
+//! impl<'de> DeserializeParams<'de> for Payjoin {
+//!     type DeserializationState = PayjoinState;
+//! }
+//!
+//! #[derive(Default)]
+//! pub struct PayjoinState {
+//!     endpoint: Option<String>,
+//! }
+//!
+//! impl<'de> DeserializationState<'de> for PayjoinState {
+//!     type Value = Payjoin;
+//!
+//!     fn is_param_known(&self, key: &str) -> bool {
+//!         key == "pj"
+//!     }
+//!
+//!     fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, PayjoinError> {
+//!         if key != "pj" {
+//!             return Ok(ParamKind::Unknown);
+//!         }
+//!         self.endpoint = Some(value.try_into().map_err(PayjoinError::Utf8)?);
+//!         Ok(ParamKind::Known)
+//!     }
+//!
+//!     fn finalize(self) -> Result<Payjoin, PayjoinError> {
+//!         let endpoint = self.endpoint.ok_or(PayjoinError::MissingEndpoint)?;
+//!         Ok(Payjoin { endpoint })
+//!     }
+//! }
+//!
+//! let uri = bip21::bip21!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?pj=https://example.com/pj", Payjoin);
+//! assert_eq!(uri.extras.endpoint, "https://example.com/pj");
+//! assert!(bip21::Uri::<'_, _, Payjoin>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd").is_err());
+//! ```
 
 use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
 use alloc::borrow::Cow;
-use alloc::string::String;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::convert::{TryFrom, TryInto};
 use bitcoin::amount::{Denomination, ParseAmountError};
 use bitcoin::address::ParseError as AddressError;
 use bitcoin::address::NetworkValidation;
 use core::fmt;
-use super::{Uri, Param};
+use super::{Uri, Param, NoExtras, SCHEME};
+use super::ser::SerializeParams;
 use percent_encoding_rfc3986::PercentDecodeError;
 
+/// Configuration of the parser.
+///
+/// The default configuration is strict - it follows BIP21 exactly.
+#[non_exhaustive]
+#[derive(Debug, Default, Clone)]
+pub struct ParseConfig {
+    /// Matches the keys of parameters defined by BIP21 case-insensitively.
+    ///
+    /// BIP21 doesn't specify case sensitivity of parameter names but some wallets produce keys like
+    /// `AMOUNT` or `Label`. When this is enabled such keys are treated as their lowercase
+    /// variants. The keys of extra parameters are passed to extras unchanged.
+    pub case_insensitive_keys: bool,
+
+    /// Requires the address to be valid for this network.
+    ///
+    /// Parsing fails if the address belongs to a different network. Since this is a runtime
+    /// setting the returned [`Uri`] is still `NetworkUnchecked` but it's safe to call
+    /// [`assume_checked`](Uri::assume_checked) on it.
+    pub network: Option<bitcoin::Network>,
+
+    /// Rejects URIs longer than this many bytes.
+    ///
+    /// The length is checked before doing anything else so this protects against malicious QR
+    /// codes wasting memory or CPU time. `None`, the default, means unlimited.
+    pub max_uri_length: Option<usize>,
+}
+
+/// Maximum number of characters of invalid scheme stored in the error.
+const MAX_SCHEME_LEN: usize = 16;
+
+/// Keys of parameters handled by the parser itself.
+const KNOWN_KEYS: [&str; 3] = ["amount", "label", "message"];
+
 impl<'a, T: DeserializeParams<'a>> Uri<'a, bitcoin::address::NetworkUnchecked, T> {
     /// Implements deserialization.
-    fn deserialize_raw(string: &'a str) -> Result<Self, Error<T::Error>> {
-        const SCHEME: &str = "bitcoin:";
-        if string.len() < SCHEME.len() {
+    ///
+    /// The scheme is matched case-insensitively as required by RFC 3986 so `BITCOIN:` produced
+    /// by QR code scanners using the alphanumeric mode is accepted.
+    fn deserialize_raw(string: &'a str, config: &ParseConfig) -> Result<Self, Error<T::Error>> {
+        Self::deserialize_raw_with(string, config, None)
+    }
+
+    /// Implements deserialization, recording recoverable errors into `warnings` if it's `Some`.
+    fn deserialize_raw_with(string: &'a str, config: &ParseConfig, warnings: Option<&mut Vec<ParseWarning>>) -> Result<Self, Error<T::Error>> {
+        if let Some(max) = config.max_uri_length {
+            if string.len() > max {
+                return Err(Error::Uri(UriError(UriErrorInner::TooLong { actual: string.len(), max })));
+            }
+        }
+        if string.len() <= SCHEME.len() {
             return Err(Error::Uri(UriError(UriErrorInner::TooShort)));
         }
 
-        if !string[..SCHEME.len()].eq_ignore_ascii_case(SCHEME) {
-            return Err(Error::Uri(UriError(UriErrorInner::InvalidScheme)));
+        let is_valid_scheme = match string.get(..SCHEME.len()) {
+            Some(scheme) => scheme.eq_ignore_ascii_case(SCHEME) && string.as_bytes()[SCHEME.len()] == b':',
+            None => false,
+        };
+        if !is_valid_scheme {
+            let found = string.find(':').map_or(string, |pos| &string[..pos]);
+            let found = found.chars().take(MAX_SCHEME_LEN).collect();
+            return Err(Error::Uri(UriError(UriErrorInner::InvalidScheme(found))));
         }
 
-        let string = &string[SCHEME.len()..];
+        let string = &string[(SCHEME.len() + 1)..];
 
         let (address, params) = match string.find('?') {
             Some(pos) => (&string[..pos], Some(&string[(pos + 1)..])),
             None => (string, None),
         };
 
-        let address = address.parse().map_err(Error::uri)?;
+        let address: bitcoin::Address<bitcoin::address::NetworkUnchecked> = address.parse().map_err(Error::uri)?;
+        let address = match config.network {
+            Some(network) => address.require_network(network).map_err(Error::uri)?.into_unchecked(),
+            None => address,
+        };
+        Uri::deserialize_params_with(address, params, config, warnings)
+    }
+}
+
+impl<'a, NetVal: NetworkValidation, T: DeserializeParams<'a>> Uri<'a, NetVal, T> {
+    /// Deserializes the query part of the URI attaching it to already parsed address.
+    fn deserialize_params(address: bitcoin::Address<NetVal>, params: Option<&'a str>, config: &ParseConfig) -> Result<Self, Error<T::Error>> {
+        Self::deserialize_params_with(address, params, config, None)
+    }
+
+    /// Deserializes the query part, recording recoverable errors into `warnings` if it's `Some`.
+    fn deserialize_params_with(
+        address: bitcoin::Address<NetVal>,
+        params: Option<&'a str>,
+        config: &ParseConfig,
+        mut warnings: Option<&mut Vec<ParseWarning>>,
+    ) -> Result<Self, Error<T::Error>> {
         let mut deserializer = T::DeserializationState::default();
         let mut amount = None;
         let mut label = None;
         let mut message = None;
+        // Raw values of the known parameters that were accepted, used to report duplicates.
+        let mut raw_amount = None;
+        let mut raw_label = None;
+        let mut raw_message = None;
         if let Some(params) = params {
             for param in params.split('&') {
-                let pos = param
-                    .find('=')
-                    .ok_or_else(|| Error::Uri(UriError(UriErrorInner::MissingEquals(param.to_owned()))))?;
+                let pos = match param.find('=') {
+                    Some(pos) => pos,
+                    None => {
+                        let error = Error::Uri(UriError(UriErrorInner::MissingEquals(param.to_owned())));
+                        recover(&mut warnings, error, || ParseWarning::MissingEquals { parameter: param.to_owned() })?;
+                        continue;
+                    },
+                };
                 let key = &param[..pos];
                 let value = &param[(pos + 1)..];
-                match key {
-                    "amount" => {
-                        let parsed_amount = bitcoin::Amount::from_str_in(value, Denomination::Bitcoin).map_err(Error::uri)?;
-                        amount = Some(parsed_amount);
+                let normalized_key = if config.case_insensitive_keys {
+                    KNOWN_KEYS.iter().find(|known| known.eq_ignore_ascii_case(key)).copied().unwrap_or(key)
+                } else {
+                    key
+                };
+                match normalized_key {
+                    "amount" => match bitcoin::Amount::from_str_in(value, Denomination::Bitcoin) {
+                        Ok(parsed_amount) => {
+                            replace_raw_value(&mut warnings, &mut raw_amount, key, value);
+                            amount = Some(parsed_amount);
+                        },
+                        Err(error) => {
+                            recover(&mut warnings, Error::uri(error), || ParseWarning::InvalidAmount {
+                                value: value.to_owned(),
+                            })?;
+                        },
                     },
-                    "label" => {
-                        let label_decoder = Param::decode(value).map_err(Error::percent_decode_static("label"))?;
-                        label = Some(label_decoder);
+                    "label" => match Param::decode(value) {
+                        Ok(label_decoder) => {
+                            replace_raw_value(&mut warnings, &mut raw_label, key, value);
+                            label = Some(label_decoder);
+                        },
+                        Err(error) => {
+                            let error = Error::percent_decode_static("label", value)(error);
+                            recover(&mut warnings, error, || ParseWarning::InvalidEncoding { key: key.to_owned() })?;
+                        },
                     },
-                    "message" => {
-                        let message_decoder = Param::decode(value).map_err(Error::percent_decode_static("message"))?;
-                        message = Some(message_decoder);
+                    "message" => match Param::decode(value) {
+                        Ok(message_decoder) => {
+                            replace_raw_value(&mut warnings, &mut raw_message, key, value);
+                            message = Some(message_decoder);
+                        },
+                        Err(error) => {
+                            let error = Error::percent_decode_static("message", value)(error);
+                            recover(&mut warnings, error, || ParseWarning::InvalidEncoding { key: key.to_owned() })?;
+                        },
                     },
                     extra_key => {
-                        let decoder = Param::decode(value).map_err(Error::percent_decode(key))?;
-                        let is_known = deserializer.deserialize_borrowed(extra_key, decoder).map_err(Error::Extras)?;
+                        let decoder = match Param::decode(value) {
+                            Ok(decoder) => decoder,
+                            Err(error) => {
+                                let error = Error::percent_decode(key, value)(error);
+                                recover(&mut warnings, error, || ParseWarning::InvalidEncoding { key: key.to_owned() })?;
+                                continue;
+                            },
+                        };
+                        let is_known = match deserializer.deserialize_borrowed(extra_key, decoder) {
+                            Ok(is_known) => is_known,
+                            Err(error) => {
+                                let error = Error::extras::<T>(error);
+                                recover(&mut warnings, error, || ParseWarning::InvalidExtra { key: key.to_owned() })?;
+                                continue;
+                            },
+                        };
                         if is_known == ParamKind::Unknown && extra_key.starts_with("req-") {
-                            return Err(Error::Uri(UriError(UriErrorInner::UnknownRequiredParameter(extra_key.to_owned()))));
+                            let error = Error::Uri(UriError(UriErrorInner::UnknownRequiredParameter(extra_key.to_owned())));
+                            recover(&mut warnings, error, || ParseWarning::UnknownRequiredParameter { key: key.to_owned() })?;
+                        } else if is_known == ParamKind::Unknown {
+                            if let Some(warnings) = warnings.as_mut() {
+                                warnings.push(ParseWarning::UnknownParameter { key: key.to_owned() });
+                            }
                         }
                     },
                 }
             }
         }
-        let extras = deserializer.finalize().map_err(Error::Extras)?;
+        if let Some(key) = deserializer.unaccepted_required_param() {
+            let error = Error::Uri(UriError(UriErrorInner::UnknownRequiredParameter(key.clone())));
+            recover(&mut warnings, error, || ParseWarning::UnknownRequiredParameter { key })?;
+        }
+        if let Err(error) = deserializer.require_fields() {
+            let fields = error.fields().to_vec();
+            recover(&mut warnings, Error::MissingRequiredExtras(error), || {
+                ParseWarning::MissingRequiredExtras { fields }
+            })?;
+        }
+        let extras = deserializer.finalize().map_err(Error::extras::<T>)?;
 
         Ok(Uri {
             address,
@@ -83,11 +289,34 @@ impl<'a, T: DeserializeParams<'a>> Uri<'a, bitcoin::address::NetworkUnchecked, T
     }
 }
 
+impl<'a, NetVal: NetworkValidation, T: DeserializeParams<'a>> Uri<'a, NetVal, T>
+where
+    for<'b> &'b T: SerializeParams,
+{
+    /// Checks that the extras don't produce a `req-` parameter they can't parse.
+    ///
+    /// Parsing rejects URIs containing unknown required parameters so a URI constructed from parts
+    /// could be rejected by the receiving side (including this library) if its extras serialize
+    /// such a parameter. This returns the same error parsing would return, without serializing the
+    /// whole URI.
+    pub fn check_no_required_unknown(&self) -> Result<(), UriError> {
+        let state = T::DeserializationState::default();
+        for (key, _) in self.extras.serialize_params() {
+            let key = key.to_string();
+            if key.starts_with("req-") && !state.is_param_known(&key) {
+                return Err(UriError(UriErrorInner::UnknownRequiredParameter(key)));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<'a, NetVal: NetworkValidation, T> Uri<'a, NetVal, T> {
     /// Makes the lifetime `'static` by converting all fields to owned.
     ///
-    /// Note that this does **not** affect `extras`!
-    fn into_static(self) -> Uri<'static, NetVal, T> {
+    /// Note that this does **not** affect `extras`! Combined with `clone` this can be used to
+    /// keep a copy of a borrowed URI: `uri.clone().into_static()`.
+    pub fn into_static(self) -> Uri<'static, NetVal, T> {
         Uri {
             address: self.address,
             amount: self.amount,
@@ -96,73 +325,952 @@ impl<'a, NetVal: NetworkValidation, T> Uri<'a, NetVal, T> {
             extras: self.extras,
         }
     }
-}
 
-/// Indicates whether a parameter with this name is known.
-///
-/// This is a semantically clear version of `bool` that also contains `#[must_use]`
-#[must_use = "param kind MUST be checked because URI with unknown req- param MUST be rejected"]
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
-pub enum ParamKind {
-    /// Signals that this parameter is known to the type being deserialized.
-    Known,
+    /// Constructs the URI from its fields, validating them.
+    ///
+    /// This is intended for building URIs programmatically when the fields come from untrusted
+    /// sources. The following checks are performed, in this order:
+    ///
+    /// * The amount must not exceed 21 million bitcoins.
+    /// * The label must decode to valid UTF-8.
+    /// * The message must decode to valid UTF-8.
+    ///
+    /// Parameters constructed from `str` or `String` are always valid UTF-8 so the last two
+    /// checks can only fail for bytes (with `non-compliant-bytes`) or pre-encoded values. Neither
+    /// the network of the address nor the extras are checked.
+    pub fn try_from_parts<L, M>(
+        address: bitcoin::Address<NetVal>,
+        amount: Option<bitcoin::Amount>,
+        label: Option<L>,
+        message: Option<M>,
+        extras: T,
+    ) -> Result<Self, UriError>
+    where
+        L: Into<Param<'a>>,
+        M: Into<Param<'a>>,
+    {
+        if let Some(amount) = amount {
+            if amount > bitcoin::Amount::MAX_MONEY {
+                return Err(UriError(UriErrorInner::ExcessiveAmount(amount)));
+            }
+        }
+        let label = label.map(Into::into);
+        let message = message.map(Into::into);
+        check_utf8("label", label.as_ref())?;
+        check_utf8("message", message.as_ref())?;
+
+        Ok(Uri {
+            address,
+            amount,
+            label,
+            message,
+            extras,
+        })
+    }
+}
+
+/// Checks that the parameter, if present, decodes to valid UTF-8.
+fn check_utf8(parameter: &'static str, value: Option<&Param<'_>>) -> Result<(), UriError> {
+    match value.map(Param::try_decode_utf8) {
+        Some(Err(error)) => Err(UriError(UriErrorInner::InvalidUtf8 { parameter, error })),
+        _ => Ok(()),
+    }
+}
+
+/// Records the warning if recovering from errors, otherwise returns the error.
+fn recover<E, F: FnOnce() -> ParseWarning>(warnings: &mut Option<&mut Vec<ParseWarning>>, error: Error<E>, warning: F) -> Result<(), Error<E>> {
+    match warnings {
+        Some(warnings) => {
+            warnings.push(warning());
+            Ok(())
+        },
+        None => Err(error),
+    }
+}
+
+/// Stores the raw value of an accepted known parameter, reporting the one it replaces.
+///
+/// Invalid values never get here so an earlier valid value is kept if a later duplicate is invalid.
+fn replace_raw_value<'a>(warnings: &mut Option<&mut Vec<ParseWarning>>, previous: &mut Option<&'a str>, key: &str, value: &'a str) {
+    if let (Some(warnings), Some(previous)) = (warnings.as_mut(), *previous) {
+        warnings.push(ParseWarning::DuplicateParameter {
+            key: key.to_owned(),
+            discarded_value: previous.to_owned(),
+        });
+    }
+    *previous = Some(value);
+}
+
+/// Problem found by [`Uri::parse_lenient_with_warnings`] that didn't prevent parsing.
+///
+/// Keys are reported as they appear in the URI, without percent-decoding.
+#[non_exhaustive]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ParseWarning {
+    /// The parameter was specified more than once, the last value is used.
+    DuplicateParameter {
+        /// Key of the parameter.
+        key: String,
+
+        /// The raw value that was overwritten.
+        discarded_value: String,
+    },
+
+    /// The parameter is not known to the parser nor the extras and it was ignored.
+    ///
+    /// This is not an error in strict parsing.
+    UnknownParameter {
+        /// Key of the parameter.
+        key: String,
+    },
+
+    /// The required parameter is not known to the parser nor the extras.
+    ///
+    /// BIP21 mandates that such URIs are rejected so the payment **must not** be made.
+    UnknownRequiredParameter {
+        /// Key of the parameter.
+        key: String,
+    },
+
+    /// The amount is invalid or has more than 8 decimal places and it was ignored.
+    ///
+    /// If a valid amount was found earlier, it's kept.
+    InvalidAmount {
+        /// The raw value of the amount.
+        value: String,
+    },
+
+    /// The value of the parameter contains invalid percent-encoding and it was ignored.
+    InvalidEncoding {
+        /// Key of the parameter.
+        key: String,
+    },
+
+    /// The extras rejected the value of the parameter.
+    InvalidExtra {
+        /// Key of the parameter.
+        key: String,
+    },
+
+    /// The parameter doesn't contain `=` and it was ignored.
+    MissingEquals {
+        /// The whole parameter.
+        parameter: String,
+    },
+
+    /// Fields required by the extras are missing.
+    ///
+    /// See [`DeserializationState::require_fields`].
+    MissingRequiredExtras {
+        /// Names of the missing fields.
+        fields: Vec<Cow<'static, str>>,
+    },
+}
+
+impl ParseWarning {
+    /// Returns `true` if the URI must not be used for payment because of this warning.
+    ///
+    /// These are the warnings that would be errors in strict parsing, except for duplicates.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, ParseWarning::DuplicateParameter { .. } | ParseWarning::UnknownParameter { .. })
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::DuplicateParameter { key, discarded_value } => {
+                write!(f, "the parameter '{}' is duplicated, discarded value '{}'", key, discarded_value)
+            },
+            ParseWarning::UnknownParameter { key } => write!(f, "the parameter '{}' is unknown", key),
+            ParseWarning::UnknownRequiredParameter { key } => write!(f, "the required parameter '{}' is unknown", key),
+            ParseWarning::InvalidAmount { value } => write!(f, "the amount '{}' is invalid", value),
+            ParseWarning::InvalidEncoding { key } => write!(f, "the parameter '{}' is not percent-encoded correctly", key),
+            ParseWarning::InvalidExtra { key } => write!(f, "the parameter '{}' has invalid value", key),
+            ParseWarning::MissingEquals { parameter } => write!(f, "the parameter '{}' is missing a value", parameter),
+            ParseWarning::MissingRequiredExtras { fields } => {
+                write!(f, "the URI is missing required parameter(s)")?;
+                let mut separator = " ";
+                for field in fields {
+                    write!(f, "{}'{}'", separator, field)?;
+                    separator = ", ";
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// URI parsed on a best-effort basis along with the problems that were found.
+///
+/// This is returned from [`Uri::parse_lenient_with_warnings`]. It's intended for displaying as
+/// much information as possible to the user, check [`is_payable`](Self::is_payable) before
+/// using it for payment.
+#[derive(Debug, Clone)]
+pub struct PartialUri<'a, T = NoExtras> {
+    /// The parsed URI, invalid parameters are missing.
+    pub uri: Uri<'a, bitcoin::address::NetworkUnchecked, T>,
+
+    /// Problems found during parsing, in the order they were encountered.
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<'a, T> PartialUri<'a, T> {
+    /// Returns `true` if none of the warnings is fatal.
+    ///
+    /// If this returns `true` the URI is the same as if it was parsed strictly, except for
+    /// duplicates and unknown parameters.
+    pub fn is_payable(&self) -> bool {
+        !self.warnings.iter().any(ParseWarning::is_fatal)
+    }
+}
+
+/// Indicates whether a parameter with this name is known.
+///
+/// This is a semantically clear version of `bool` that also contains `#[must_use]`
+#[must_use = "param kind MUST be checked because URI with unknown req- param MUST be rejected"]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ParamKind {
+    /// Signals that this parameter is known to the type being deserialized.
+    Known,
+
+    /// Signals that this parameter is **not** known to the type being deserialized.
+    ///
+    /// Parsing error will be reported if this is returned from `req-` parameter as mandated by
+    /// BIP21.
+    Unknown,
+}
+
+impl ParamKind {
+    /// Returns `true` if the parameter is [`Known`](ParamKind::Known).
+    pub fn is_known(self) -> bool {
+        self == ParamKind::Known
+    }
+
+    /// Returns `true` if the parameter is [`Unknown`](ParamKind::Unknown).
+    pub fn is_unknown(self) -> bool {
+        self == ParamKind::Unknown
+    }
+}
+
+/// Converts `true` to `Known` and `false` to `Unknown`.
+///
+/// This is handy for implementations forwarding to [`DeserializationState::is_param_known`].
+impl From<bool> for ParamKind {
+    fn from(value: bool) -> Self {
+        if value {
+            ParamKind::Known
+        } else {
+            ParamKind::Unknown
+        }
+    }
+}
+
+/// Converts `Known` to `true` and `Unknown` to `false`.
+impl From<ParamKind> for bool {
+    fn from(value: ParamKind) -> Self {
+        value.is_known()
+    }
+}
+
+impl fmt::Display for ParamKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamKind::Known => write!(f, "Known"),
+            ParamKind::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Defines error type of deserialization.
+///
+/// This is a separate trait to ensure the error is same for all lifetimes.
+pub trait DeserializationError {
+    /// The error returned when deserialization fails.
+    type Error;
+
+    /// Extracts [`UriError`] from the error if the extras failed to parse a nested URI.
+    ///
+    /// Parsing reports the returned error as [`Error::Uri`] instead of wrapping it in
+    /// [`Error::Extras`], which avoids two levels of URI errors when displaying. The default
+    /// implementation returns the error unchanged.
+    fn into_uri_error(error: Self::Error) -> Result<UriError, Self::Error> {
+        Err(error)
+    }
+}
+
+/// Represents the state of deserialization of extras.
+///
+/// A new state is created using `Default` for each parsed URI, see the
+/// [module documentation](self) for an example.
+#[doc(alias = "parse")]
+pub trait DeserializationState<'de>: Default {
+    /// Value returned when deserialization finishes.
+    type Value: DeserializationError;
+
+    /// Returns `true` if the parameter is known.
+    ///
+    /// Required parameters include the `req-` prefix.
+    fn is_param_known(&self, key: &str) -> bool;
+
+    /// Deserializes a temporary.
+    ///
+    /// This can not borrow the key nor value, so has to clone them or throw away.
+    /// Required parameters include the `req-` prefix.
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error>;
+
+    /// Deserializes a borrowed value possibly avoiding cloning.
+    ///
+    /// Implementing this can enable zero-copy deserialization.
+    /// Required parameters include the `req-` prefix.
+    ///
+    /// The default implementation forwards to `deserialize_temp`
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.deserialize_temp(key, value)
+    }
+
+    /// Signals that all parameters were processed.
+    ///
+    /// This function may perform additional validation - e.g. checking if some mandatory fields are missing.
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error>;
+
+    /// Returns the keys of parameters deserialized so far.
+    ///
+    /// This is intended for diagnostics and testing, the default implementation doesn't track
+    /// anything and returns an empty slice. See [`RecordingState`] for an implementation that does.
+    fn visited_keys(&self) -> &[String] {
+        &[]
+    }
+
+    /// Checks that all required fields were deserialized.
+    ///
+    /// This allows separating missing fields from invalid values - errors are reported as
+    /// [`Error::MissingRequiredExtras`]. The default implementation doesn't require any fields.
+    ///
+    /// This is called after all parameters were processed but **before**
+    /// [`finalize`](Self::finalize) because `finalize` consumes the state. So if both methods
+    /// check the same field the error from this one is reported and `finalize` is not called.
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        Ok(())
+    }
+
+    /// Returns a `req-` parameter that was reported as known but the final value won't contain.
+    ///
+    /// This is needed by states that decide which parameters they understand only after seeing
+    /// all of them, such as [`EitherState`]. The URI is then rejected the same way as if the
+    /// parameter was reported as unknown. It's called after all parameters were processed, before
+    /// [`require_fields`](Self::require_fields). The default implementation returns `None`.
+    fn unaccepted_required_param(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Represents a value that can be deserialized.
+///
+/// All values passed in `Extras` type parameter of [`Uri`] must implement this trait to allow
+/// deserialization.
+pub trait DeserializeParams<'de>: Sized + DeserializationError {
+    /// State used when deserializing.
+    type DeserializationState: DeserializationState<'de, Value = Self>;
+}
+
+/// Extras recording the keys of all parameters passed to their deserializer.
+///
+/// Note that only the extra parameters reach the deserializer of extras - `amount`, `label` and
+/// `message` are handled by the parser itself and are never recorded.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct Recorded<T> {
+    /// The extras produced by the inner deserialization state.
+    pub extras: T,
+
+    /// Keys of parameters in the order they were encountered, including the `req-` prefix.
+    pub visited_keys: Vec<String>,
+}
+
+impl<T: DeserializationError> DeserializationError for Recorded<T> {
+    type Error = T::Error;
+
+    fn into_uri_error(error: Self::Error) -> Result<UriError, Self::Error> {
+        T::into_uri_error(error)
+    }
+}
+
+impl<'de, T: DeserializeParams<'de>> DeserializeParams<'de> for Recorded<T> {
+    type DeserializationState = RecordingState<T::DeserializationState>;
+}
+
+/// Deserialization state that tracks all keys delegated to the inner state `S`.
+///
+/// This is the state of [`Recorded`] and it returns the tracked keys from
+/// [`visited_keys`](DeserializationState::visited_keys).
+#[derive(Debug, Default, Clone)]
+pub struct RecordingState<S> {
+    inner: S,
+    visited_keys: Vec<String>,
+}
+
+impl<'de, S: DeserializationState<'de>> DeserializationState<'de> for RecordingState<S> {
+    type Value = Recorded<S::Value>;
+
+    fn is_param_known(&self, key: &str) -> bool {
+        self.inner.is_param_known(key)
+    }
+
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.visited_keys.push(key.to_owned());
+        self.inner.deserialize_temp(key, value)
+    }
+
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.visited_keys.push(key.to_owned());
+        self.inner.deserialize_borrowed(key, value)
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        Ok(Recorded {
+            extras: self.inner.finalize()?,
+            visited_keys: self.visited_keys,
+        })
+    }
+
+    fn visited_keys(&self) -> &[String] {
+        &self.visited_keys
+    }
+
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        self.inner.require_fields()
+    }
+
+    fn unaccepted_required_param(&self) -> Option<String> {
+        self.inner.unaccepted_required_param()
+    }
+}
+
+/// Extras storing all parameters that are unknown to their deserializer.
+///
+/// This is useful when forwarding URIs between systems - the parameters this crate or the inner
+/// extras don't understand are preserved instead of being discarded and they are displayed after
+/// the inner extras. Unknown `req-` parameters are still rejected by the parser.
+#[derive(Debug, Default, Clone)]
+pub struct Forwarded<T> {
+    /// The extras produced by the inner deserialization state.
+    pub extras: T,
+
+    /// Unknown parameters in the order they were encountered.
+    pub unknown_params: Vec<(String, Param<'static>)>,
+}
+
+impl<T: DeserializationError> DeserializationError for Forwarded<T> {
+    type Error = T::Error;
+
+    fn into_uri_error(error: Self::Error) -> Result<UriError, Self::Error> {
+        T::into_uri_error(error)
+    }
+}
+
+impl<'de, T: DeserializeParams<'de>> DeserializeParams<'de> for Forwarded<T> {
+    type DeserializationState = ForwardingState<T::DeserializationState>;
+}
+
+/// Deserialization state that stores all parameters unknown to the inner state `S`.
+///
+/// This is the state of [`Forwarded`].
+#[derive(Debug, Default, Clone)]
+pub struct ForwardingState<S> {
+    inner: S,
+    unknown_params: Vec<(String, Param<'static>)>,
+}
+
+impl<S> ForwardingState<S> {
+    /// Stores the parameter if it's unknown.
+    fn collect_unknown(&mut self, kind: ParamKind, key: &str, value: Param<'_>) -> ParamKind {
+        if kind == ParamKind::Unknown {
+            self.unknown_params.push((key.to_owned(), value.into_static()));
+        }
+        kind
+    }
+}
+
+impl<'de, S: DeserializationState<'de>> DeserializationState<'de> for ForwardingState<S> {
+    type Value = Forwarded<S::Value>;
+
+    fn is_param_known(&self, key: &str) -> bool {
+        self.inner.is_param_known(key)
+    }
+
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        let kind = self.inner.deserialize_temp(key, value.reborrow())?;
+        Ok(self.collect_unknown(kind, key, value))
+    }
+
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        let kind = self.inner.deserialize_borrowed(key, value.clone())?;
+        Ok(self.collect_unknown(kind, key, value))
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        Ok(Forwarded {
+            extras: self.inner.finalize()?,
+            unknown_params: self.unknown_params,
+        })
+    }
+
+    fn visited_keys(&self) -> &[String] {
+        self.inner.visited_keys()
+    }
+
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        self.inner.require_fields()
+    }
+
+    fn unaccepted_required_param(&self) -> Option<String> {
+        self.inner.unaccepted_required_param()
+    }
+}
+
+impl<T: DeserializationError> DeserializationError for Box<T> {
+    type Error = T::Error;
+
+    fn into_uri_error(error: Self::Error) -> Result<UriError, Self::Error> {
+        T::into_uri_error(error)
+    }
+}
+
+impl<'de, T: DeserializeParams<'de>> DeserializeParams<'de> for Box<T> {
+    type DeserializationState = BoxedState<T::DeserializationState>;
+}
+
+/// Deserialization state of boxed extras.
+///
+/// This delegates everything to the inner state `S` and boxes the value in
+/// [`finalize`](DeserializationState::finalize).
+#[derive(Debug, Default, Clone)]
+pub struct BoxedState<S>(S);
+
+impl<'de, S: DeserializationState<'de>> DeserializationState<'de> for BoxedState<S> {
+    type Value = Box<S::Value>;
+
+    fn is_param_known(&self, key: &str) -> bool {
+        self.0.is_param_known(key)
+    }
+
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.0.deserialize_temp(key, value)
+    }
+
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.0.deserialize_borrowed(key, value)
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        self.0.finalize().map(Box::new)
+    }
+
+    fn visited_keys(&self) -> &[String] {
+        self.0.visited_keys()
+    }
+
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        self.0.require_fields()
+    }
+
+    fn unaccepted_required_param(&self) -> Option<String> {
+        self.0.unaccepted_required_param()
+    }
+}
+
+/// Allows sharing the state through a shared reference.
+///
+/// Mutating methods access the inner state directly since they already have exclusive access.
+/// Note that [`visited_keys`](DeserializationState::visited_keys) can not be forwarded through
+/// the borrow so it always returns an empty slice.
+impl<'de, S: DeserializationState<'de>> DeserializationState<'de> for core::cell::RefCell<S> {
+    type Value = S::Value;
+
+    fn is_param_known(&self, key: &str) -> bool {
+        self.borrow().is_param_known(key)
+    }
+
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.get_mut().deserialize_temp(key, value)
+    }
+
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.get_mut().deserialize_borrowed(key, value)
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        self.into_inner().finalize()
+    }
+
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        self.borrow().require_fields()
+    }
+
+    fn unaccepted_required_param(&self) -> Option<String> {
+        self.borrow().unaccepted_required_param()
+    }
+}
+
+/// Allows sharing the state across threads.
+///
+/// A poisoned mutex is not considered an error - the state is used as-is. Mutating methods access
+/// the inner state directly since they already have exclusive access. Note that
+/// [`visited_keys`](DeserializationState::visited_keys) can not be forwarded through the lock so
+/// it always returns an empty slice.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl<'de, S: DeserializationState<'de>> DeserializationState<'de> for std::sync::Mutex<S> {
+    type Value = S::Value;
+
+    fn is_param_known(&self, key: &str) -> bool {
+        self.lock().unwrap_or_else(std::sync::PoisonError::into_inner).is_param_known(key)
+    }
+
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .deserialize_temp(key, value)
+    }
+
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        self.get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .deserialize_borrowed(key, value)
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        self.into_inner().unwrap_or_else(std::sync::PoisonError::into_inner).finalize()
+    }
+
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        self.lock().unwrap_or_else(std::sync::PoisonError::into_inner).require_fields()
+    }
+
+    fn unaccepted_required_param(&self) -> Option<String> {
+        self.lock().unwrap_or_else(std::sync::PoisonError::into_inner).unaccepted_required_param()
+    }
+}
+
+/// Errors are reported from the side that failed.
+#[cfg(feature = "either")]
+#[cfg_attr(docsrs, doc(cfg(feature = "either")))]
+impl<A: DeserializationError, B: DeserializationError> DeserializationError for either::Either<A, B> {
+    type Error = either::Either<A::Error, B::Error>;
+
+    fn into_uri_error(error: Self::Error) -> Result<UriError, Self::Error> {
+        match error {
+            either::Either::Left(error) => A::into_uri_error(error).map_err(either::Either::Left),
+            either::Either::Right(error) => B::into_uri_error(error).map_err(either::Either::Right),
+        }
+    }
+}
+
+/// Deserializes `Left` unless a parameter recognized only by `B` is encountered.
+#[cfg(feature = "either")]
+#[cfg_attr(docsrs, doc(cfg(feature = "either")))]
+impl<'de, A: DeserializeParams<'de>, B: DeserializeParams<'de>> DeserializeParams<'de> for either::Either<A, B> {
+    type DeserializationState = EitherState<A::DeserializationState, B::DeserializationState>;
+}
+
+/// Deserialization state of [`Either`](either::Either).
+///
+/// All parameters are passed to both states. The `Right` variant is selected once a parameter
+/// recognized by `B` but not by `A` is encountered, `Left` is selected otherwise. If the
+/// selected variant doesn't recognize a `req-` parameter recognized by the other one the URI is
+/// rejected.
+#[cfg(feature = "either")]
+#[cfg_attr(docsrs, doc(cfg(feature = "either")))]
+#[derive(Debug, Default, Clone)]
+pub struct EitherState<A, B> {
+    left: A,
+    right: B,
+    right_selected: bool,
+    /// `req-` parameters recognized only by `A`.
+    left_only_required: Vec<String>,
+    /// `req-` parameters recognized only by `B`.
+    right_only_required: Vec<String>,
+}
+
+#[cfg(feature = "either")]
+impl<A, B> EitherState<A, B> {
+    /// Returns the state of the variant that is currently selected.
+    pub fn determine_variant(&self) -> either::Either<&A, &B> {
+        if self.right_selected {
+            either::Either::Right(&self.right)
+        } else {
+            either::Either::Left(&self.left)
+        }
+    }
+
+    /// Selects the variant based on the kinds returned from both states.
+    fn select(&mut self, key: &str, left: ParamKind, right: ParamKind) -> ParamKind {
+        if left.is_unknown() && right.is_known() {
+            self.right_selected = true;
+        }
+        if key.starts_with("req-") && left.is_known() != right.is_known() {
+            let only_required = if left.is_known() {
+                &mut self.left_only_required
+            } else {
+                &mut self.right_only_required
+            };
+            only_required.push(key.to_owned());
+        }
+        (left.is_known() || right.is_known()).into()
+    }
+}
+
+#[cfg(feature = "either")]
+impl<'de, A: DeserializationState<'de>, B: DeserializationState<'de>> DeserializationState<'de> for EitherState<A, B> {
+    type Value = either::Either<A::Value, B::Value>;
+
+    fn is_param_known(&self, key: &str) -> bool {
+        self.left.is_param_known(key) || self.right.is_param_known(key)
+    }
+
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        let left = self.left.deserialize_temp(key, value.clone()).map_err(either::Either::Left)?;
+        let right = self.right.deserialize_temp(key, value).map_err(either::Either::Right)?;
+        Ok(self.select(key, left, right))
+    }
+
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        let left = self.left.deserialize_borrowed(key, value.clone()).map_err(either::Either::Left)?;
+        let right = self.right.deserialize_borrowed(key, value).map_err(either::Either::Right)?;
+        Ok(self.select(key, left, right))
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        if self.right_selected {
+            self.right.finalize().map(either::Either::Right).map_err(either::Either::Right)
+        } else {
+            self.left.finalize().map(either::Either::Left).map_err(either::Either::Left)
+        }
+    }
+
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        if self.right_selected {
+            self.right.require_fields()
+        } else {
+            self.left.require_fields()
+        }
+    }
+
+    fn unaccepted_required_param(&self) -> Option<String> {
+        if self.right_selected {
+            self.left_only_required
+                .first()
+                .cloned()
+                .or_else(|| self.right.unaccepted_required_param())
+        } else {
+            self.right_only_required
+                .first()
+                .cloned()
+                .or_else(|| self.left.unaccepted_required_param())
+        }
+    }
+}
+
+/// Type representing a parameter key.
+///
+/// This is used by [`RequiredKeys`] implementations for tuples.
+pub trait ParamKey {
+    /// The key as it appears in the URI, including the `req-` prefix if required.
+    const KEY: &'static str;
+}
+
+/// Keys that must be present in the URI.
+///
+/// This is implemented for tuples of up to four [`ParamKey`] types.
+pub trait RequiredKeys {
+    /// Returns the keys that must be present.
+    fn required_keys() -> &'static [&'static str];
+}
+
+macro_rules! impl_required_keys_for_tuple {
+    ($($key:ident),*) => {
+        impl<$($key: ParamKey),*> RequiredKeys for ($($key,)*) {
+            fn required_keys() -> &'static [&'static str] {
+                struct Keys<$($key),*>(core::marker::PhantomData<($($key,)*)>);
+
+                impl<$($key: ParamKey),*> Keys<$($key),*> {
+                    const KEYS: &'static [&'static str] = &[$($key::KEY),*];
+                }
+
+                Keys::<$($key),*>::KEYS
+            }
+        }
+    };
+}
+
+impl_required_keys_for_tuple!(A);
+impl_required_keys_for_tuple!(A, B);
+impl_required_keys_for_tuple!(A, B, C);
+impl_required_keys_for_tuple!(A, B, C, D);
+
+/// Extras requiring that the parameters specified by `R` are present.
+///
+/// The parameters must be present and recognized by the inner extras `T`, otherwise parsing
+/// fails with [`Error::MissingRequiredExtras`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct RequiredExtras<T, R: RequiredKeys>(pub T, core::marker::PhantomData<R>);
+
+impl<T, R: RequiredKeys> RequiredExtras<T, R> {
+    /// Wraps the extras.
+    pub fn new(extras: T) -> Self {
+        RequiredExtras(extras, Default::default())
+    }
+
+    /// Returns the inner extras.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializationError, R: RequiredKeys> DeserializationError for RequiredExtras<T, R> {
+    type Error = T::Error;
+
+    fn into_uri_error(error: Self::Error) -> Result<UriError, Self::Error> {
+        T::into_uri_error(error)
+    }
+}
+
+impl<'de, T: DeserializeParams<'de>, R: RequiredKeys> DeserializeParams<'de> for RequiredExtras<T, R> {
+    type DeserializationState = RequiredState<T::DeserializationState, R>;
+}
+
+/// Deserialization state checking that all keys required by `R` were recognized by `S`.
+///
+/// This is the state of [`RequiredExtras`].
+#[derive(Debug, Clone)]
+pub struct RequiredState<S, R> {
+    inner: S,
+    found: Vec<&'static str>,
+    _required: core::marker::PhantomData<R>,
+}
+
+impl<S: Default, R> Default for RequiredState<S, R> {
+    fn default() -> Self {
+        RequiredState {
+            inner: S::default(),
+            found: Vec::new(),
+            _required: Default::default(),
+        }
+    }
+}
+
+impl<S, R: RequiredKeys> RequiredState<S, R> {
+    /// Records the key if it's required and it was recognized.
+    fn record(&mut self, key: &str, kind: ParamKind) -> ParamKind {
+        if kind.is_known() {
+            if let Some(required) = R::required_keys().iter().find(|required| **required == key) {
+                self.found.push(required);
+            }
+        }
+        kind
+    }
+}
+
+impl<'de, S: DeserializationState<'de>, R: RequiredKeys> DeserializationState<'de> for RequiredState<S, R> {
+    type Value = RequiredExtras<S::Value, R>;
+
+    fn is_param_known(&self, key: &str) -> bool {
+        self.inner.is_param_known(key)
+    }
+
+    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        let kind = self.inner.deserialize_temp(key, value)?;
+        Ok(self.record(key, kind))
+    }
+
+    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+        let kind = self.inner.deserialize_borrowed(key, value)?;
+        Ok(self.record(key, kind))
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        self.inner.finalize().map(RequiredExtras::new)
+    }
+
+    fn visited_keys(&self) -> &[String] {
+        self.inner.visited_keys()
+    }
 
-    /// Signals that this parameter is **not** known to the type being deserialized.
-    ///
-    /// Parsing error will be reported if this is returned from `req-` parameter as mandated by
-    /// BIP21.
-    Unknown,
+    fn require_fields(&self) -> Result<(), MissingFieldsError> {
+        let mut missing = match self.inner.require_fields() {
+            Ok(()) => Vec::new(),
+            Err(error) => error.into_fields(),
+        };
+        let required = R::required_keys().iter().filter(|required| !self.found.contains(required));
+        missing.extend(required.map(|required| Cow::Borrowed(*required)));
+        MissingFieldsError::check(missing)
+    }
+
+    fn unaccepted_required_param(&self) -> Option<String> {
+        self.inner.unaccepted_required_param()
+    }
 }
 
-/// Defines error type of deserialization.
+/// Error returned when required fields of extras are missing.
 ///
-/// This is a separate trait to ensure the error is same for all lifetimes.
-pub trait DeserializationError {
-    /// The error returned when deserialization fails.
-    type Error;
+/// This is returned from [`DeserializationState::require_fields`] and reported as
+/// [`Error::MissingRequiredExtras`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MissingFieldsError {
+    fields: Vec<Cow<'static, str>>,
 }
 
-/// Represents the state of deserialization of extras.
-pub trait DeserializationState<'de>: Default {
-    /// Value returned when deserialization finishes.
-    type Value: DeserializationError;
-
-    /// Returns `true` if the parameter is known.
-    ///
-    /// Required parameters include the `req-` prefix.
-    fn is_param_known(&self, key: &str) -> bool;
+impl MissingFieldsError {
+    /// Creates the error from the names of missing fields.
+    pub fn new<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'static, str>>,
+    {
+        MissingFieldsError {
+            fields: fields.into_iter().map(Into::into).collect(),
+        }
+    }
 
-    /// Deserializes a temporary.
+    /// Returns an error if `fields` is not empty.
     ///
-    /// This can not borrow the key nor value, so has to clone them or throw away.
-    /// Required parameters include the `req-` prefix.
-    fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error>;
+    /// This is useful for implementing [`DeserializationState::require_fields`] of composed
+    /// states.
+    pub fn check(fields: Vec<Cow<'static, str>>) -> Result<(), Self> {
+        if fields.is_empty() {
+            Ok(())
+        } else {
+            Err(MissingFieldsError { fields })
+        }
+    }
 
-    /// Deserializes a borrowed value possibly avoiding cloning.
-    ///
-    /// Implementing this can enable zero-copy deserialization.
-    /// Required parameters include the `req-` prefix.
-    ///
-    /// The default implementation forwards to `deserialize_temp`
-    fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
-        self.deserialize_temp(key, value)
+    /// Returns the names of missing fields.
+    pub fn fields(&self) -> &[Cow<'static, str>] {
+        &self.fields
     }
 
-    /// Signals that all parameters were processed.
-    ///
-    /// This function may perform additional validation - e.g. checking if some mandatory fields are missing.
-    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error>;
+    /// Returns the names of missing fields by value.
+    pub fn into_fields(self) -> Vec<Cow<'static, str>> {
+        self.fields
+    }
 }
 
-/// Represents a value that can be deserialized.
-///
-/// All values passed in `Extras` type parameter of [`Uri`] must implement this trait to allow
-/// deserialization.
-pub trait DeserializeParams<'de>: Sized + DeserializationError {
-    /// State used when deserializing.
-    type DeserializationState: DeserializationState<'de, Value = Self>;
+impl fmt::Display for MissingFieldsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the URI is missing required parameter(s)")?;
+        let mut separator = " ";
+        for field in &self.fields {
+            write!(f, "{}'{}'", separator, field)?;
+            separator = ", ";
+        }
+        Ok(())
+    }
 }
 
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for MissingFieldsError {}
+
 /// Error returned when parsing URI.
 #[derive(Clone, Debug)]
 pub enum Error<T> {
@@ -174,6 +1282,10 @@ pub enum Error<T> {
     ///
     /// This only directly forwards parsing error from extras.
     Extras(T),
+    /// Required fields of extras are missing.
+    ///
+    /// This is reported by [`DeserializationState::require_fields`].
+    MissingRequiredExtras(MissingFieldsError),
 }
 
 impl<T> Error<T> {
@@ -181,32 +1293,110 @@ impl<T> Error<T> {
         Error::Uri(UriError(error.into()))
     }
 
-    fn percent_decode_static(parameter: &'static str) -> impl FnOnce(PercentDecodeError) -> Self {
+    /// Reports the error as [`Error::Uri`] if the extras failed to parse a nested URI.
+    fn extras<D: DeserializationError<Error = T>>(error: T) -> Self {
+        match D::into_uri_error(error) {
+            Ok(error) => Error::Uri(error),
+            Err(error) => Error::Extras(error),
+        }
+    }
+
+    fn percent_decode_static<'s>(parameter: &'static str, value: &'s str) -> impl 's + FnOnce(PercentDecodeError) -> Self {
         move |error| {
             Self::uri(UriErrorInner::PercentDecode {
                 parameter: Cow::Borrowed(parameter),
+                context: percent_decode_context(value, &error),
                 error,
             })
         }
     }
 
-    fn percent_decode(parameter: &str) -> impl '_ + FnOnce(PercentDecodeError) -> Self {
+    fn percent_decode<'s>(parameter: &'s str, value: &'s str) -> impl 's + FnOnce(PercentDecodeError) -> Self {
         move |error| {
             Self::uri(UriErrorInner::PercentDecode {
                 parameter: parameter.to_owned().into(),
+                context: percent_decode_context(value, &error),
                 error,
             })
         }
     }
 }
 
+/// Maximum number of bytes of the value around the malformed sequence stored in the error.
+const MAX_PERCENT_DECODE_CONTEXT_LEN: usize = 32;
+
+/// Returns the part of `value` surrounding the position of `error`.
+///
+/// The returned string is at most `MAX_PERCENT_DECODE_CONTEXT_LEN` bytes long to avoid leaking
+/// too much of possibly sensitive data.
+fn percent_decode_context(value: &str, error: &PercentDecodeError) -> String {
+    let position = match error {
+        PercentDecodeError::InvalidHexDigit { position, .. } => *position,
+        PercentDecodeError::MissingDigits { .. } => value.len(),
+    };
+    let mut start = position.saturating_sub(MAX_PERCENT_DECODE_CONTEXT_LEN / 2);
+    let mut end = (start + MAX_PERCENT_DECODE_CONTEXT_LEN).min(value.len());
+    start = end.saturating_sub(MAX_PERCENT_DECODE_CONTEXT_LEN);
+    // Only shrink the range so that the limit is never exceeded.
+    while !value.is_char_boundary(start) {
+        start += 1;
+    }
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value[start..end].to_owned()
+}
+
+impl<T> From<core::convert::Infallible> for Error<T> {
+    fn from(value: core::convert::Infallible) -> Self {
+        match value {}
+    }
+}
+
+/// Allows using `?` on [`UriError`] in functions returning [`Error`].
+impl<T> From<UriError> for Error<T> {
+    fn from(value: UriError) -> Self {
+        Error::Uri(value)
+    }
+}
+
+/// Without `std` the underlying error is always included in the message because there's no other
+/// way to access it. With `std` it's only available through `source()` unless the alternate flag
+/// (`{:#}`) is used, which appends the whole chain of sources separated by `: `.
 impl<T: fmt::Display> fmt::Display for Error<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Uri(_) => write!(f, "invalid BIP21 URI"),
-            Error::Extras(_) => write!(f, "failed to parse extra argument(s)"),
+            Error::Uri(_) => write!(f, "invalid BIP21 URI")?,
+            Error::Extras(_) => write!(f, "failed to parse extra argument(s)")?,
+            Error::MissingRequiredExtras(_) => write!(f, "required extra argument(s) are missing")?,
+        }
+        #[cfg(feature = "std")]
+        if f.alternate() {
+            match self {
+                Error::Uri(error) => write_chain(f, error)?,
+                Error::Extras(error) => write!(f, ": {:#}", error)?,
+                Error::MissingRequiredExtras(error) => write_chain(f, error)?,
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        match self {
+            Error::Uri(error) => write!(f, ": {}", error)?,
+            Error::Extras(error) => write!(f, ": {}", error)?,
+            Error::MissingRequiredExtras(error) => write!(f, ": {}", error)?,
         }
+        Ok(())
+    }
+}
+
+/// Writes the error and all its sources, each prefixed with `: `.
+#[cfg(feature = "std")]
+fn write_chain(f: &mut fmt::Formatter, error: &(dyn std::error::Error + 'static)) -> fmt::Result {
+    let mut error = Some(error);
+    while let Some(current) = error {
+        write!(f, ": {}", current)?;
+        error = current.source();
     }
+    Ok(())
 }
 
 #[cfg(feature = "std")]
@@ -215,26 +1405,45 @@ impl<T: fmt::Display + std::error::Error + 'static> std::error::Error for Error<
         match self {
             Error::Uri(error) => Some(error),
             Error::Extras(error) => Some(error),
+            Error::MissingRequiredExtras(error) => Some(error),
         }
     }
 }
 
 /// Error returned when parsing non-extras parts of URI.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct UriError(UriErrorInner);
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 enum UriErrorInner {
     TooShort,
-    InvalidScheme,
+    TooLong {
+        actual: usize,
+        max: usize,
+    },
+    /// The found scheme, truncated to `MAX_SCHEME_LEN` characters.
+    InvalidScheme(String),
     Address(AddressError),
     Amount(ParseAmountError),
     UnknownRequiredParameter(String),
     PercentDecode {
         parameter: Cow<'static, str>,
+        /// The part of the value surrounding the malformed sequence.
+        context: String,
         error: PercentDecodeError,
     },
     MissingEquals(String),
+    ExcessiveAmount(bitcoin::Amount),
+    InvalidUtf8 {
+        parameter: &'static str,
+        error: core::str::Utf8Error,
+    },
+}
+
+impl From<core::convert::Infallible> for UriError {
+    fn from(value: core::convert::Infallible) -> Self {
+        match value {}
+    }
 }
 
 impl From<AddressError> for UriErrorInner {
@@ -249,19 +1458,157 @@ impl From<ParseAmountError> for UriErrorInner {
     }
 }
 
+/// Maximum number of characters of parsed strings shown in debug output of [`UriError`].
+const MAX_DEBUG_LEN: usize = 64;
+
+/// Debug-formats a string truncated to `MAX_DEBUG_LEN` characters.
+struct Truncated<'a>(&'a str);
+
+impl fmt::Debug for Truncated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.char_indices().nth(MAX_DEBUG_LEN) {
+            Some((pos, _)) => write!(f, "{:?}...", &self.0[..pos]),
+            None => write!(f, "{:?}", self.0),
+        }
+    }
+}
+
+/// Shows only the publicly-relevant details, truncating parsed strings.
+impl fmt::Debug for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            UriErrorInner::TooShort => write!(f, "UriError::TooShort"),
+            UriErrorInner::TooLong { actual, max } => f.debug_struct("UriError::TooLong").field("actual", actual).field("max", max).finish(),
+            UriErrorInner::InvalidScheme(found) => f.debug_tuple("UriError::InvalidScheme").field(&Truncated(found)).finish(),
+            UriErrorInner::Address(error) => f.debug_tuple("UriError::Address").field(error).finish(),
+            UriErrorInner::Amount(error) => f.debug_tuple("UriError::Amount").field(error).finish(),
+            UriErrorInner::UnknownRequiredParameter(parameter) => {
+                f.debug_tuple("UriError::UnknownRequiredParameter").field(&Truncated(parameter)).finish()
+            },
+            UriErrorInner::PercentDecode { parameter, context, error } => f
+                .debug_struct("UriError::PercentDecode")
+                .field("parameter", &Truncated(parameter))
+                .field("context", context)
+                .field("error", error)
+                .finish(),
+            UriErrorInner::MissingEquals(parameter) => f.debug_tuple("UriError::MissingEquals").field(&Truncated(parameter)).finish(),
+            UriErrorInner::ExcessiveAmount(amount) => f.debug_tuple("UriError::ExcessiveAmount").field(amount).finish(),
+            UriErrorInner::InvalidUtf8 { parameter, error } => f
+                .debug_struct("UriError::InvalidUtf8")
+                .field("parameter", parameter)
+                .field("error", error)
+                .finish(),
+        }
+    }
+}
+
 impl fmt::Display for UriError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self.0 {
             UriErrorInner::TooShort => write!(f, "the URI is too short"),
-            UriErrorInner::InvalidScheme => write!(f, "the URI has invalid scheme"),
+            UriErrorInner::TooLong { actual, max } => write!(f, "the URI is {} bytes long, the maximum is {}", actual, max),
+            UriErrorInner::InvalidScheme(found) => write!(f, "the URI has invalid scheme '{}', expected 'bitcoin'", found),
+            #[cfg(feature = "std")]
             UriErrorInner::Address(_) => write!(f, "the address is invalid"),
+            #[cfg(not(feature = "std"))]
+            UriErrorInner::Address(error) => write!(f, "the address is invalid: {}", error),
+            #[cfg(feature = "std")]
             UriErrorInner::Amount(_) => write!(f, "the amount is invalid"),
+            #[cfg(not(feature = "std"))]
+            UriErrorInner::Amount(error) => write!(f, "the amount is invalid: {}", error),
             UriErrorInner::UnknownRequiredParameter(parameter) => write!(f, "the URI contains unknown required parameter '{}'", parameter),
             #[cfg(feature = "std")]
-            UriErrorInner::PercentDecode { parameter, error: _ } => write!(f, "can not percent-decode parameter {}", parameter),
+            UriErrorInner::PercentDecode {
+                parameter,
+                context,
+                error: _,
+            } => {
+                write!(f, "can not percent-decode parameter {} near '{}'", parameter, context)
+            },
             #[cfg(not(feature = "std"))]
-            UriErrorInner::PercentDecode { parameter, error } => write!(f, "can not percent-decode parameter {}: {}", parameter, error),
+            UriErrorInner::PercentDecode { parameter, context, error } => {
+                write!(f, "can not percent-decode parameter {} near '{}': {}", parameter, context, error)
+            },
             UriErrorInner::MissingEquals(parameter) => write!(f, "the parameter '{}' is missing a value", parameter),
+            UriErrorInner::ExcessiveAmount(amount) => write!(
+                f,
+                "the amount {} exceeds the supply of bitcoin",
+                amount.display_in(Denomination::Bitcoin).show_denomination()
+            ),
+            #[cfg(feature = "std")]
+            UriErrorInner::InvalidUtf8 { parameter, error: _ } => write!(f, "the {} is not valid UTF-8", parameter),
+            #[cfg(not(feature = "std"))]
+            UriErrorInner::InvalidUtf8 { parameter, error } => write!(f, "the {} is not valid UTF-8: {}", parameter, error),
+        }
+    }
+}
+
+impl UriError {
+    /// Returns the parameter that is missing the `=` character if that's the cause of the error.
+    pub fn missing_equals_token(&self) -> Option<&str> {
+        match &self.0 {
+            UriErrorInner::MissingEquals(parameter) => Some(parameter),
+            _ => None,
+        }
+    }
+
+    /// Returns the beginning of the scheme that was found if the scheme is invalid.
+    ///
+    /// This is the part of the input before the first `:`, truncated to at most 16 characters.
+    pub fn invalid_scheme_found(&self) -> Option<&str> {
+        match &self.0 {
+            UriErrorInner::InvalidScheme(found) => Some(found),
+            _ => None,
+        }
+    }
+
+    /// Returns the address error if the address is invalid.
+    ///
+    /// This includes addresses belonging to a different network than required. The error can be
+    /// used to tell users what exactly is wrong with the address, e.g. an invalid checksum.
+    pub fn as_address_error(&self) -> Option<&AddressError> {
+        match &self.0 {
+            UriErrorInner::Address(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns the address error if the address is invalid, consuming `self`.
+    ///
+    /// See [`as_address_error`](Self::as_address_error) for details.
+    pub fn into_address_error(self) -> Option<AddressError> {
+        match self.0 {
+            UriErrorInner::Address(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns the percent-decoding error if a parameter contained invalid percent-encoding.
+    pub fn percent_decode_error(&self) -> Option<&PercentDecodeError> {
+        match &self.0 {
+            UriErrorInner::PercentDecode { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the parameter which failed to percent-decode.
+    ///
+    /// This is `label`, `message` or the key of the extra parameter and it can be used to tell
+    /// users which part of the URI is malformed.
+    pub fn percent_decode_parameter(&self) -> Option<&str> {
+        match &self.0 {
+            UriErrorInner::PercentDecode { parameter, .. } => Some(parameter),
+            _ => None,
+        }
+    }
+
+    /// Returns the part of the parameter value surrounding the malformed percent-encoded sequence.
+    ///
+    /// This is at most 32 bytes long and it's intended for debugging.
+    pub fn percent_decode_context(&self) -> Option<&str> {
+        match &self.0 {
+            UriErrorInner::PercentDecode { context, .. } => Some(context),
+            _ => None,
         }
     }
 }
@@ -272,30 +1619,197 @@ impl std::error::Error for UriError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self.0 {
             UriErrorInner::TooShort => None,
-            UriErrorInner::InvalidScheme => None,
+            UriErrorInner::TooLong { .. } => None,
+            UriErrorInner::InvalidScheme(_) => None,
             UriErrorInner::Address(error) => Some(error),
             UriErrorInner::Amount(error) => Some(error),
             UriErrorInner::UnknownRequiredParameter(_) => None,
-            UriErrorInner::PercentDecode { parameter: _, error } => Some(error),
+            UriErrorInner::PercentDecode { .. } => self.percent_decode_error().map(|error| error as _),
             UriErrorInner::MissingEquals(_) => None,
+            UriErrorInner::ExcessiveAmount(_) => None,
+            UriErrorInner::InvalidUtf8 { error, .. } => Some(error),
         }
     }
 }
 
+/// The scheme is matched case-insensitively.
+///
 /// **Warning**: this implementation may needlessly allocate, consider using `TryFrom<&str>` instead.
 impl<'a, T: for<'de> DeserializeParams<'de>> core::str::FromStr for Uri<'a, bitcoin::address::NetworkUnchecked, T> {
     type Err = Error<T::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Uri::deserialize_raw(s).map(Uri::into_static)
+        Uri::deserialize_raw(s, &ParseConfig::default()).map(Uri::into_static)
+    }
+}
+
+impl<T: for<'de> DeserializeParams<'de>> Uri<'static, bitcoin::address::NetworkUnchecked, T> {
+    /// Parses the URI converting all parameters to owned.
+    ///
+    /// This is the same as the `FromStr` implementation. Contrary to [`parse`](Self::parse) the
+    /// returned value doesn't borrow the input, which may require allocations.
+    #[inline]
+    pub fn parse_str(s: &str) -> Result<Self, Error<T::Error>> {
+        s.parse()
+    }
+}
+
+/// Converts the parsing error into a serde error, keeping the details.
+#[cfg(all(feature = "serde", feature = "std"))]
+fn serde_error<E: serde::de::Error, T: fmt::Display>(error: Error<T>) -> E {
+    match error {
+        Error::Uri(error) => E::custom(error),
+        Error::Extras(error) => E::custom(error),
+        Error::MissingRequiredExtras(error) => E::custom(error),
+    }
+}
+
+/// Amount in bitcoins represented either as a number or a string.
+#[cfg(all(feature = "serde", feature = "std"))]
+struct SerdeAmount(bitcoin::Amount);
+
+#[cfg(all(feature = "serde", feature = "std"))]
+impl<'de> serde::Deserialize<'de> for SerdeAmount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = SerdeAmount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an amount in bitcoins")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                bitcoin::Amount::from_str_in(value, Denomination::Bitcoin)
+                    .map(SerdeAmount)
+                    .map_err(E::custom)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                self.visit_str(&value.to_string())
+            }
+
+            // Display of f64 never uses the exponent notation and it produces the shortest string
+            // that round-trips so parsing it is exact, unlike multiplying by 100 000 000.
+            fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                self.visit_str(&value.to_string())
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+/// Deserializes the URI from a string or a map of fields.
+///
+/// The string is parsed the same way as with `FromStr`. The map must contain the `address` key
+/// and it may contain `amount` (in bitcoins, either a number or a string), `label` and
+/// `message`. Other keys are passed to the extras, their values must be strings.
+///
+/// This requires a self-describing format such as JSON.
+#[cfg(all(feature = "serde", feature = "std"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "serde", feature = "std"))))]
+impl<'de, T> serde::Deserialize<'de> for Uri<'static, bitcoin::address::NetworkUnchecked, T>
+where
+    T: for<'a> DeserializeParams<'a>,
+    T::Error: fmt::Display,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        struct Visitor<T>(core::marker::PhantomData<T>);
+
+        impl<'de, T> serde::de::Visitor<'de> for Visitor<T>
+        where
+            T: for<'a> DeserializeParams<'a>,
+            T::Error: fmt::Display,
+        {
+            type Value = Uri<'static, bitcoin::address::NetworkUnchecked, T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a BIP21 URI string or a map of its fields")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Uri::parse_str(value).map_err(serde_error)
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut state = <T as DeserializeParams<'static>>::DeserializationState::default();
+                let mut address = None;
+                let mut amount = None;
+                let mut label = None;
+                let mut message = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match &*key {
+                        "address" if address.is_some() => return Err(A::Error::duplicate_field("address")),
+                        "address" => {
+                            let value = map.next_value::<String>()?;
+                            let parsed = value.parse::<bitcoin::Address<bitcoin::address::NetworkUnchecked>>();
+                            address = Some(parsed.map_err(|error| serde_error::<_, T::Error>(Error::uri(error)))?);
+                        },
+                        "amount" if amount.is_some() => return Err(A::Error::duplicate_field("amount")),
+                        "amount" => amount = Some(map.next_value::<SerdeAmount>()?.0),
+                        "label" if label.is_some() => return Err(A::Error::duplicate_field("label")),
+                        "label" => label = Some(map.next_value::<Param<'static>>()?),
+                        "message" if message.is_some() => return Err(A::Error::duplicate_field("message")),
+                        "message" => message = Some(map.next_value::<Param<'static>>()?),
+                        _ => {
+                            let value = map.next_value::<String>()?;
+                            let kind = state
+                                .deserialize_temp(&key, value.into())
+                                .map_err(|error| serde_error(Error::Extras(error)))?;
+                            if kind == ParamKind::Unknown && key.starts_with("req-") {
+                                return Err(serde_error::<_, T::Error>(Error::uri(UriErrorInner::UnknownRequiredParameter(key))));
+                            }
+                        },
+                    }
+                }
+                let address = address.ok_or_else(|| A::Error::missing_field("address"))?;
+                if let Some(key) = state.unaccepted_required_param() {
+                    return Err(serde_error::<_, T::Error>(Error::uri(UriErrorInner::UnknownRequiredParameter(key))));
+                }
+                state
+                    .require_fields()
+                    .map_err(|error| serde_error::<_, T::Error>(Error::MissingRequiredExtras(error)))?;
+                let extras = state.finalize().map_err(|error| serde_error(Error::Extras(error)))?;
+
+                Ok(Uri {
+                    address,
+                    amount,
+                    label,
+                    message,
+                    extras,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(Visitor(core::marker::PhantomData))
+    }
+}
+
+/// Attaches the parameters to already parsed address.
+///
+/// The string is the query part of the URI **without** the leading `?`. This is useful if you
+/// validated the address separately and want to avoid parsing it again.
+impl<'a, T: DeserializeParams<'a>> TryFrom<(bitcoin::Address, &'a str)> for Uri<'a, bitcoin::address::NetworkChecked, T> {
+    type Error = Error<T::Error>;
+
+    fn try_from((address, params): (bitcoin::Address, &'a str)) -> Result<Self, Self::Error> {
+        let params = if params.is_empty() { None } else { Some(params) };
+        Self::deserialize_params(address, params, &ParseConfig::default())
     }
 }
 
+/// Zero-copy parsing, the same as [`Uri::parse`].
+///
+/// The scheme is matched case-insensitively.
 impl<'a, T: DeserializeParams<'a>> TryFrom<&'a str> for Uri<'a, bitcoin::address::NetworkUnchecked, T> {
     type Error = Error<T::Error>;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
-        Self::deserialize_raw(s)
+        Self::deserialize_raw(s, &ParseConfig::default())
     }
 }
 
@@ -303,15 +1817,23 @@ impl<'a, T: DeserializeParams<'a>> TryFrom<&'a str> for Uri<'a, bitcoin::address
 impl<'a, T: for<'de> DeserializeParams<'de>> TryFrom<String> for Uri<'a, bitcoin::address::NetworkUnchecked, T> {
     type Error = Error<T::Error>;
 
+    #[inline]
     fn try_from(s: String) -> Result<Self, Self::Error> {
         s.parse()
     }
 }
 
-/// **Warning**: this implementation may needlessly allocate, consider using `TryFrom<&str>` instead.
+/// Parses borrowed strings without copying and owned strings the same way as `TryFrom<String>`.
+///
+/// If `s` is [`Cow::Borrowed`] this is zero-copy - `label` and `message` borrow from the input and
+/// are decoded lazily, just like with `TryFrom<&str>`.
+///
+/// **Warning**: if `s` is [`Cow::Owned`] this implementation may needlessly allocate, consider
+/// using `TryFrom<&str>` instead.
 impl<'a, T: for<'de> DeserializeParams<'de>> TryFrom<Cow<'a, str>> for Uri<'a, bitcoin::address::NetworkUnchecked, T> {
     type Error = Error<T::Error>;
 
+    #[inline]
     fn try_from(s: Cow<'a, str>) -> Result<Self, Self::Error> {
         match s {
             Cow::Borrowed(s) => s.try_into(),
@@ -321,6 +1843,85 @@ impl<'a, T: for<'de> DeserializeParams<'de>> TryFrom<Cow<'a, str>> for Uri<'a, b
 }
 
 impl<'a, T: DeserializeParams<'a>> Uri<'a, bitcoin::address::NetworkUnchecked, T> {
+    /// Parses the URI borrowing from the input string.
+    ///
+    /// This is the same as the `TryFrom<&str>` implementation and it's zero-copy - the parameters
+    /// are decoded lazily and borrow from `s`. Use [`parse_str`](Self::parse_str) if you need
+    /// `'static` lifetime.
+    ///
+    /// The scheme is matched case-insensitively, see [`parse_lenient`](Self::parse_lenient).
+    #[inline]
+    pub fn parse(s: &'a str) -> Result<Self, Error<T::Error>> {
+        Self::deserialize_raw(s, &ParseConfig::default())
+    }
+
+    /// Parses the URI accepting the scheme in any case.
+    ///
+    /// QR code scanners and some wallets produce URIs starting with `BITCOIN:` or `Bitcoin:`.
+    /// Schemes are case-insensitive according to RFC 3986 so all of these are accepted. The keys
+    /// of parameters are still matched exactly unless configured otherwise using
+    /// [`parse_with_config`](Self::parse_with_config).
+    ///
+    /// This is currently the same as [`parse`](Self::parse) and exists to make the intent
+    /// explicit.
+    ///
+    /// ```
+    /// let uri: bip21::Uri<'_, _> = bip21::Uri::parse_lenient("BITCOIN:1andreas3batLhQa2FawWjeyjCqyBzypd").unwrap();
+    /// assert_eq!(uri.address.assume_checked().to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+    /// ```
+    #[inline]
+    pub fn parse_lenient(s: &'a str) -> Result<Self, Error<T::Error>> {
+        Self::parse(s)
+    }
+
+    /// Parses the URI borrowing from the input string and checks the network of the address.
+    ///
+    /// This is the same as calling [`parse`](Self::parse) followed by
+    /// [`require_network`](Self::require_network).
+    pub fn parse_with_network(s: &'a str, network: bitcoin::Network) -> Result<Uri<'a, bitcoin::address::NetworkChecked, T>, Error<T::Error>> {
+        Self::parse(s)?.require_network(network)
+    }
+
+    /// Parses the URI recovering from errors in parameters where possible.
+    ///
+    /// Invalid parameters are skipped and reported as [`ParseWarning`]s, unknown parameters and
+    /// missing required fields of extras are reported as well. This is intended for user
+    /// interfaces that want to show all available information even from a malformed URI.
+    ///
+    /// Only these errors can not be recovered from and they are still returned:
+    ///
+    /// * The URI is too short or has invalid scheme.
+    /// * The address is invalid.
+    /// * Finalizing the extras failed ([`Error::Extras`]).
+    ///
+    /// **Important:** the URI must not be used for payment if
+    /// [`PartialUri::is_payable`] returns `false`.
+    pub fn parse_lenient_with_warnings(s: &'a str) -> Result<PartialUri<'a, T>, Error<T::Error>> {
+        let mut warnings = Vec::new();
+        let uri = Self::deserialize_raw_with(s, &ParseConfig::default(), Some(&mut warnings))?;
+        Ok(PartialUri { uri, warnings })
+    }
+
+    /// Parses the URI borrowing from the input string, rejecting it if it's longer than `max_len`.
+    ///
+    /// This is the same as [`parse_with_config`](Self::parse_with_config) with
+    /// [`max_uri_length`](ParseConfig::max_uri_length) set. The length is in bytes.
+    pub fn parse_with_limit(s: &'a str, max_len: usize) -> Result<Self, Error<T::Error>> {
+        let config = ParseConfig {
+            max_uri_length: Some(max_len),
+            ..Default::default()
+        };
+        Self::deserialize_raw(s, &config)
+    }
+
+    /// Parses the URI borrowing from the input string using custom configuration.
+    ///
+    /// This is the same as [`parse`](Self::parse) with the behavior of the parser tweaked
+    /// according to `config`.
+    pub fn parse_with_config(s: &'a str, config: &ParseConfig) -> Result<Self, Error<T::Error>> {
+        Self::deserialize_raw(s, config)
+    }
+
     /// Checks whether network of this address is as required.
     ///
     /// For details about this mechanism, see section [*parsing addresses*](bitcoin::Address#parsing-addresses) on [`bitcoin::Address`].
@@ -345,4 +1946,90 @@ impl<'a, T: DeserializeParams<'a>> Uri<'a, bitcoin::address::NetworkUnchecked, T
             extras: self.extras,
         }
     }
+
+    /// Performs all built-in checks and marks the URI as validated.
+    ///
+    /// This checks the network of the address, that the amount doesn't exceed 21 million
+    /// bitcoins, that the label and message are valid UTF-8 and that the extras don't produce
+    /// unknown `req-` parameters (see [`check_no_required_unknown`](Uri::check_no_required_unknown)).
+    /// The last two are already guaranteed for parsed URIs (except UTF-8 with the
+    /// `non-compliant-bytes` feature) but not for URIs constructed manually. The parameters are
+    /// converted to owned ones so the returned value is not bound to the input.
+    pub fn validate(self, network: bitcoin::Network) -> Result<ValidatedUri<T>, Error<T::Error>>
+    where
+        for<'b> &'b T: SerializeParams,
+    {
+        self.validate_with(network, &NoValidator)
+    }
+
+    /// Performs all built-in checks followed by custom checks implemented by `validator`.
+    ///
+    /// Errors returned by the validator are reported as [`Error::Extras`].
+    pub fn validate_with<V: Validator<T> + ?Sized>(self, network: bitcoin::Network, validator: &V) -> Result<ValidatedUri<T>, Error<T::Error>>
+    where
+        for<'b> &'b T: SerializeParams,
+    {
+        let uri = self.require_network(network)?;
+        if let Some(amount) = uri.amount {
+            if amount > bitcoin::Amount::MAX_MONEY {
+                return Err(Error::Uri(UriError(UriErrorInner::ExcessiveAmount(amount))));
+            }
+        }
+        check_utf8("label", uri.label.as_ref()).map_err(Error::Uri)?;
+        check_utf8("message", uri.message.as_ref()).map_err(Error::Uri)?;
+        uri.check_no_required_unknown().map_err(Error::Uri)?;
+        let uri = uri.into_static();
+        validator.validate(&uri).map_err(Error::Extras)?;
+        Ok(ValidatedUri(uri))
+    }
+}
+
+/// URI that passed all checks.
+///
+/// This can only be constructed by calling [`Uri::validate`] or [`Uri::validate_with`] so
+/// accepting this type guarantees the URI passed the checks listed there, no matter whether it
+/// was parsed or constructed manually. It dereferences to the inner [`Uri`].
+#[derive(Debug, Clone)]
+pub struct ValidatedUri<T = NoExtras>(Uri<'static, bitcoin::address::NetworkChecked, T>);
+
+impl<T> ValidatedUri<T> {
+    /// Returns the inner URI.
+    pub fn into_inner(self) -> Uri<'static, bitcoin::address::NetworkChecked, T> {
+        self.0
+    }
+}
+
+impl<T> core::ops::Deref for ValidatedUri<T> {
+    type Target = Uri<'static, bitcoin::address::NetworkChecked, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Custom validation of URIs performed by [`Uri::validate_with`].
+///
+/// This is implemented for closures too.
+pub trait Validator<T: DeserializationError> {
+    /// Checks the URI and returns an error if it's not valid.
+    fn validate(&self, uri: &Uri<'static, bitcoin::address::NetworkChecked, T>) -> Result<(), T::Error>;
+}
+
+impl<T, F> Validator<T> for F
+where
+    T: DeserializationError,
+    F: Fn(&Uri<'static, bitcoin::address::NetworkChecked, T>) -> Result<(), T::Error>,
+{
+    fn validate(&self, uri: &Uri<'static, bitcoin::address::NetworkChecked, T>) -> Result<(), T::Error> {
+        self(uri)
+    }
+}
+
+/// Validator that doesn't perform any additional checks.
+struct NoValidator;
+
+impl<T: DeserializationError> Validator<T> for NoValidator {
+    fn validate(&self, _uri: &Uri<'static, bitcoin::address::NetworkChecked, T>) -> Result<(), T::Error> {
+        Ok(())
+    }
 }
@@ -5,7 +5,7 @@
 //!
 //! * Rust-idiomatic: uses strong types, standard traits and other things
 //! * Compliant: implements all requirements of BIP21, including protections to not forget about
-//!              `req-`. (But see features.)
+//!   `req-`. (But see features.)
 //! * Flexible: enables parsing/serializing additional arguments not defined by BIP21
 //! * Performant: uses zero-copy deserialization and lazy evaluation wherever possible.
 //!
@@ -17,10 +17,30 @@
 //!
 //! The crate is `no_std` but does require `alloc`.
 //!
+//! ## Type aliases
+//!
+//! The full type of [`Uri`] is verbose so these aliases are provided:
+//!
+//! * [`CheckedUri<'a, T>`](CheckedUri) - URI with the network of the address checked.
+//! * [`UncheckedUri<'a, T>`](UncheckedUri) - URI with the network not checked yet, as returned
+//!   from parsing.
+//! * [`StaticUri<T>`](StaticUri) - fully-owned URI with the network checked.
+//!
+//! `T` defaults to [`NoExtras`] in all of them.
+//!
 //! ## Features
 //!
 //! * `std` enables integration with `std` - mainly `std::error::Error`.
 //! * `non-compliant-bytes` - enables use of non-compliant API that can parse non-UTF-8 URI values.
+//!   Implies `either`.
+//! * `either` - implements serialization and deserialization for [`Either`](either::Either) extras.
+//! * `test-utils` - implements `Default` for [`Uri`] using a hardcoded address, for tests only.
+//! * `serde` - implements `Serialize` and `Deserialize` for [`Param`] and, together with `std`,
+//!   `Deserialize` for [`Uri`].
+//! * `base64` - adds base64url conversions to [`Param`] for extension parameters carrying binary
+//!   data. Decoding additionally requires `non-compliant-bytes` because the decoded data is not
+//!   necessarily UTF-8.
+//! * `heapless` - implements conversions between [`Param`] and fixed-capacity `heapless` types.
 //!
 //! ## Stabilization roadmap
 //!
@@ -41,7 +61,11 @@ extern crate std;
 
 extern crate alloc;
 
+#[macro_use]
+mod macros;
 pub mod de;
+pub mod merge;
+pub mod queue;
 pub mod ser;
 
 use alloc::borrow::ToOwned;
@@ -55,8 +79,25 @@ use either::Either;
 use core::convert::{TryFrom, TryInto};
 use bitcoin::address::NetworkValidation;
 
-pub use de::{DeserializeParams, DeserializationState, DeserializationError};
+pub use de::{DeserializeParams, DeserializationState, DeserializationError, ParseConfig};
 pub use ser::SerializeParams;
+/// Re-exported for use with [`Param::re_encode`].
+pub use percent_encoding_rfc3986::AsciiSet;
+/// Re-exported as a base for building an [`AsciiSet`] without depending on
+/// `percent_encoding_rfc3986`.
+pub use percent_encoding_rfc3986::{CONTROLS, NON_ALPHANUMERIC};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec::{self, Vec};
+    pub use bitcoin::address::NetworkUnchecked;
+}
+
+/// The URI scheme defined by BIP21, without the trailing colon.
+///
+/// The parser matches it case-insensitively, the serializer always writes it in lowercase.
+pub const SCHEME: &str = "bitcoin";
 
 /// Parsed BIP21 URI.
 ///
@@ -66,7 +107,7 @@ pub use ser::SerializeParams;
 /// ## Parsing
 ///
 /// `Uri` implements `FromStr` so you can simply use `s.parse::<Uri<'static>>()`. However that is
-/// not zero-copy. If you wish to use zero-copy parsing call `try_into()` instead.
+/// not zero-copy. If you wish to use zero-copy parsing call `try_into()` or [`Uri::parse`] instead.
 ///
 /// ## Displaying
 ///
@@ -102,6 +143,19 @@ where
     pub extras: Extras,
 }
 
+/// [`Uri`] with the network of the address checked.
+pub type CheckedUri<'a, T = NoExtras> = Uri<'a, bitcoin::address::NetworkChecked, T>;
+
+/// [`Uri`] with the network of the address not checked yet.
+///
+/// This is what the parsing functions return.
+pub type UncheckedUri<'a, T = NoExtras> = Uri<'a, bitcoin::address::NetworkUnchecked, T>;
+
+/// Fully-owned [`Uri`] with the network of the address checked.
+///
+/// Use [`Uri::into_static`] to obtain it from a borrowed one.
+pub type StaticUri<T = NoExtras> = Uri<'static, bitcoin::address::NetworkChecked, T>;
+
 impl<'a, NetVal: NetworkValidation, T: Default> Uri<'a, NetVal, T> {
     /// Creates an URI with defaults.
     ///
@@ -118,7 +172,35 @@ impl<'a, NetVal: NetworkValidation, T: Default> Uri<'a, NetVal, T> {
     }
 }
 
+/// Mainnet address used by the `Default` implementation of [`Uri`].
+///
+/// This is a segwit v1 (taproot) address from BIP86 test vectors.
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub const DEFAULT_ADDRESS: &str = "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr";
+
+/// Creates an URI with [`DEFAULT_ADDRESS`] and no other fields.
+///
+/// This is intended for tests and mocks only, hence the feature gate.
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+impl<T: Default> Default for Uri<'static, bitcoin::address::NetworkChecked, T> {
+    fn default() -> Self {
+        let address = DEFAULT_ADDRESS
+            .parse::<bitcoin::Address<bitcoin::address::NetworkUnchecked>>()
+            .expect("the default address is valid")
+            .assume_checked();
+        Uri::new(address)
+    }
+}
+
 impl<'a, NetVal: NetworkValidation, T> Uri<'a, NetVal, T> {
+    /// Returns the URI scheme, this is always [`SCHEME`].
+    #[inline]
+    pub fn scheme() -> &'static str {
+        SCHEME
+    }
+
     /// Creates an URI with defaults.
     ///
     /// This sets all fields except `address` and `extras` to default values.
@@ -132,6 +214,261 @@ impl<'a, NetVal: NetworkValidation, T> Uri<'a, NetVal, T> {
             extras,
         }
     }
+
+    /// Creates an URI with extras constructed from the address.
+    ///
+    /// This is the preferred way of creating URIs when constructing the extras can fail or
+    /// needs the address - e.g. when the extras contain an invoice paying to the same address.
+    /// Other fields are set to default values, same as in [`with_extras`](Self::with_extras).
+    pub fn try_with_extras<E, F>(address: bitcoin::Address<NetVal>, f: F) -> Result<Self, E>
+    where
+        F: FnOnce(&bitcoin::Address<NetVal>) -> Result<T, E>,
+    {
+        let extras = f(&address)?;
+        Ok(Uri::with_extras(address, extras))
+    }
+
+    /// Sets the amount, returning the modified URI.
+    pub fn with_amount(mut self, amount: bitcoin::Amount) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// Sets the amount in satoshis, returning the modified URI.
+    ///
+    /// This avoids the need to import [`bitcoin::Amount`] when the amount is available as
+    /// a number of satoshis.
+    pub fn with_amount_sat(self, sat: u64) -> Self {
+        self.with_amount(bitcoin::Amount::from_sat(sat))
+    }
+
+    /// Returns the address provided in the URI.
+    pub fn address(&self) -> &bitcoin::Address<NetVal> {
+        &self.address
+    }
+
+    /// Returns the type of the address, if it's a standard one.
+    ///
+    /// This can be used to check whether the wallet supports paying to the address, e.g. if it
+    /// requires SegWit or Taproot. The type doesn't depend on the network so it's available
+    /// even if the network wasn't checked yet. See [`bitcoin::Address::address_type`] for details.
+    pub fn address_type(&self) -> Option<bitcoin::AddressType> {
+        self.address.as_unchecked().assume_checked_ref().address_type()
+    }
+
+    /// Consumes the URI returning just the address.
+    pub fn into_address(self) -> bitcoin::Address<NetVal> {
+        self.address
+    }
+
+    /// Compares the fields of two URIs returning which of them changed.
+    ///
+    /// Parameters are compared by their decoded values. Extras are **not** compared because they
+    /// may not implement `PartialEq`, compare them separately if needed.
+    pub fn diff<U>(&self, other: &Uri<'_, NetVal, U>) -> UriDiff {
+        UriDiff {
+            address_changed: self.address.as_unchecked() != other.address.as_unchecked(),
+            amount_changed: self.amount != other.amount,
+            label_changed: self.label != other.label,
+            message_changed: self.message != other.message,
+        }
+    }
+
+    /// Returns `true` if both URIs have the same fields, including extras.
+    ///
+    /// Parameters are compared by their decoded values so the order of parameters in the
+    /// original strings and the way they were encoded don't matter, unlike when comparing the
+    /// strings. Use [`diff`](Self::diff) to find out which fields differ.
+    pub fn semantically_equal<U>(&self, other: &Uri<'_, NetVal, U>) -> bool
+    where
+        T: PartialEq<U>,
+    {
+        !self.diff(other).has_changes() && self.extras == other.extras
+    }
+
+    /// Returns a copy of the URI without the label and message.
+    ///
+    /// The label and message are free-form text that may contain personal information such as
+    /// real names or descriptions of purchases so they should not end up in logs. The redacted
+    /// URI can be logged instead: `log::info!("Received URI: {}", uri.redact())`.
+    ///
+    /// The address and amount are preserved and so are extras, which usually carry payment
+    /// instructions rather than personal information. If your extras may contain sensitive data
+    /// you need to clear them separately.
+    pub fn redact(&self) -> Uri<'static, NetVal, T>
+    where
+        bitcoin::Address<NetVal>: Clone,
+        T: Clone,
+    {
+        Uri {
+            address: self.address.clone(),
+            amount: self.amount,
+            label: None,
+            message: None,
+            extras: self.extras.clone(),
+        }
+    }
+
+    /// Converts the URI into a plain struct with owned fields, discarding extras.
+    ///
+    /// The label and message are decoded replacing invalid UTF-8 sequences, see
+    /// [`Param::decode_utf8_lossy`].
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn to_payment_info(&self) -> PaymentInfo {
+        use alloc::string::ToString;
+
+        PaymentInfo {
+            address: self.address.as_unchecked().assume_checked_ref().to_string(),
+            amount_sat: self.amount.map(bitcoin::Amount::to_sat),
+            label: self.label.as_ref().map(|label| label.decode_utf8_lossy().into_owned()),
+            message: self.message.as_ref().map(|message| message.decode_utf8_lossy().into_owned()),
+        }
+    }
+}
+
+impl<'a, T> Uri<'a, bitcoin::address::NetworkChecked, T> {
+    /// Marks the URI as not validated.
+    ///
+    /// This is always safe and it's useful for storing checked and unchecked URIs together. The
+    /// network can be checked again using [`require_network`](Uri::require_network).
+    pub fn into_unchecked(self) -> Uri<'a, bitcoin::address::NetworkUnchecked, T> {
+        Uri {
+            address: self.address.into_unchecked(),
+            amount: self.amount,
+            label: self.label,
+            message: self.message,
+            extras: self.extras,
+        }
+    }
+}
+
+/// Marks the URI as not validated, the same as [`Uri::into_unchecked`].
+impl<'a, T> From<Uri<'a, bitcoin::address::NetworkChecked, T>> for Uri<'a, bitcoin::address::NetworkUnchecked, T> {
+    #[inline]
+    fn from(value: Uri<'a, bitcoin::address::NetworkChecked, T>) -> Self {
+        value.into_unchecked()
+    }
+}
+
+/// Extracts the address, discarding all other fields.
+///
+/// Combined with [`require_network`](Uri::require_network) this validates the network too:
+/// `bitcoin::Address::from(uri.require_network(network)?)`.
+impl<'a, NetVal: NetworkValidation, T> From<Uri<'a, NetVal, T>> for bitcoin::Address<NetVal> {
+    fn from(value: Uri<'a, NetVal, T>) -> Self {
+        value.address
+    }
+}
+
+/// Same as [`Uri::semantically_equal`].
+impl<'a, 'b, NetVal: NetworkValidation, T: PartialEq<U>, U> PartialEq<Uri<'b, NetVal, U>> for Uri<'a, NetVal, T> {
+    fn eq(&self, other: &Uri<'b, NetVal, U>) -> bool {
+        self.semantically_equal(other)
+    }
+}
+
+impl<'a, NetVal: NetworkValidation, T: Eq> Eq for Uri<'a, NetVal, T> {}
+
+/// Compares the fields of the URI one by one, see the `Ord` implementation.
+impl<'a, 'b, NetVal: NetworkValidation, T: PartialOrd> PartialOrd<Uri<'b, NetVal, T>> for Uri<'a, NetVal, T> {
+    fn partial_cmp(&self, other: &Uri<'b, NetVal, T>) -> Option<core::cmp::Ordering> {
+        let ordering = self
+            .address
+            .as_unchecked()
+            .cmp(other.address.as_unchecked())
+            .then_with(|| self.amount.cmp(&other.amount))
+            .then_with(|| cmp_params(&self.label, &other.label))
+            .then_with(|| cmp_params(&self.message, &other.message));
+        match ordering {
+            core::cmp::Ordering::Equal => self.extras.partial_cmp(&other.extras),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// Compares the fields of the URI one by one.
+///
+/// The fields are compared in this order: address, amount, label, message and extras. Missing
+/// fields are less than present ones and parameters are compared by their decoded bytes. This
+/// order is consistent with `PartialEq` but it is otherwise arbitrary - e.g. it doesn't consider
+/// which URI pays more.
+impl<'a, NetVal: NetworkValidation, T: Ord> Ord for Uri<'a, NetVal, T> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.address
+            .as_unchecked()
+            .cmp(other.address.as_unchecked())
+            .then_with(|| self.amount.cmp(&other.amount))
+            .then_with(|| cmp_params(&self.label, &other.label))
+            .then_with(|| cmp_params(&self.message, &other.message))
+            .then_with(|| self.extras.cmp(&other.extras))
+    }
+}
+
+/// Compares optional parameters with different lifetimes, `None` is the least.
+fn cmp_params(a: &Option<Param<'_>>, b: &Option<Param<'_>>) -> core::cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.decoded_bytes().cmp(b.decoded_bytes()),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// Hashes the decoded values of parameters, consistent with `PartialEq`.
+impl<'a, NetVal: NetworkValidation, T: core::hash::Hash> core::hash::Hash for Uri<'a, NetVal, T> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.address.as_unchecked().hash(state);
+        self.amount.hash(state);
+        self.label.hash(state);
+        self.message.hash(state);
+        self.extras.hash(state);
+    }
+}
+
+/// Fields that differ between two URIs.
+///
+/// This is returned from [`Uri::diff`].
+#[non_exhaustive]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct UriDiff {
+    /// The addresses are different.
+    pub address_changed: bool,
+
+    /// The amounts are different or only one URI has an amount.
+    pub amount_changed: bool,
+
+    /// The labels are different or only one URI has a label.
+    pub label_changed: bool,
+
+    /// The messages are different or only one URI has a message.
+    pub message_changed: bool,
+}
+
+impl UriDiff {
+    /// Returns `true` if any of the compared fields changed.
+    pub fn has_changes(&self) -> bool {
+        self.address_changed || self.amount_changed || self.label_changed || self.message_changed
+    }
+}
+
+/// Payment information extracted from [`Uri`] without any BIP21-specific types.
+///
+/// This is returned from [`Uri::to_payment_info`] and is intended to be passed to code that
+/// doesn't need to know about BIP21. Extras are not included.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PaymentInfo {
+    /// The address in its usual string representation.
+    pub address: String,
+
+    /// The requested amount in satoshis.
+    pub amount_sat: Option<u64>,
+
+    /// The decoded label.
+    pub label: Option<String>,
+
+    /// The decoded message.
+    pub message: Option<String>,
 }
 
 /// Abstracted stringly parameter in the URI.
@@ -146,7 +483,125 @@ pub struct Param<'a>(ParamInner<'a>);
 impl<'a> Param<'a> {
     /// Convenience constructor.
     fn decode(s: &'a str) -> Result<Self, PercentDecodeError> {
-        Ok(Param(ParamInner::EncodedBorrowed(percent_encoding_rfc3986::percent_decode_str(s)?)))
+        Ok(Param(ParamInner::EncodedBorrowed(percent_encoding_rfc3986::percent_decode_str(s)?, s)))
+    }
+
+    /// Creates an empty parameter.
+    ///
+    /// This is the same as `Param::default()`.
+    pub fn empty() -> Param<'static> {
+        Param(ParamInner::UnencodedString(Cow::Borrowed("")))
+    }
+
+    /// Creates a parameter from formatting arguments.
+    ///
+    /// This is a clearer alternative to `format!(...).into()`. The arguments are formatted into a
+    /// `String` unless they are a plain string literal, in which case no allocation happens.
+    ///
+    /// **Panics** if a formatting trait implementation returns an error, use
+    /// [`try_from_fmt`](Self::try_from_fmt) to handle it.
+    pub fn from_fmt_args(args: core::fmt::Arguments<'_>) -> Param<'static> {
+        match args.as_str() {
+            Some(literal) => literal.into(),
+            None => alloc::fmt::format(args).into(),
+        }
+    }
+
+    /// Creates a parameter from formatting arguments returning an error if formatting fails.
+    pub fn try_from_fmt(args: core::fmt::Arguments<'_>) -> Result<Param<'static>, core::fmt::Error> {
+        use core::fmt::Write;
+
+        if let Some(literal) = args.as_str() {
+            return Ok(literal.into());
+        }
+        let mut string = String::new();
+        string.write_fmt(args)?;
+        Ok(string.into())
+    }
+
+    /// Returns an iterator over decoded bytes.
+    ///
+    /// This is always available unlike the public [`bytes`](Self::bytes) method.
+    fn decoded_bytes(&self) -> impl '_ + Iterator<Item = u8> {
+        let (decoder, unencoded) = match &self.0 {
+            ParamInner::EncodedBorrowed(decoder, _) => (Some(decoder.clone()), &[][..]),
+            ParamInner::UnencodedBytes(bytes) => (None, &**bytes),
+            ParamInner::UnencodedString(string) => (None, string.as_bytes()),
+        };
+        decoder.into_iter().flatten().chain(unencoded.iter().copied())
+    }
+
+    /// Calls `f` with consecutive chunks of decoded bytes, stopping at the first error.
+    ///
+    /// Percent-encoded parameters are decoded in small chunks on the stack, other parameters are
+    /// passed in a single chunk.
+    fn try_for_each_decoded_chunk<E, F: FnMut(&[u8]) -> Result<(), E>>(&self, mut f: F) -> Result<(), E> {
+        match &self.0 {
+            ParamInner::EncodedBorrowed(decoder, _) => {
+                let mut buf = [0u8; 64];
+                let mut len = 0;
+                for byte in decoder.clone() {
+                    buf[len] = byte;
+                    len += 1;
+                    if len == buf.len() {
+                        f(&buf)?;
+                        len = 0;
+                    }
+                }
+                f(&buf[..len])
+            },
+            ParamInner::UnencodedBytes(bytes) => f(bytes),
+            ParamInner::UnencodedString(string) => f(string.as_bytes()),
+        }
+    }
+
+    /// Computes SHA-256 of the decoded bytes without collecting them.
+    ///
+    /// This is useful for extras carrying values that are committed to by their hash.
+    pub fn decoded_sha256(&self) -> [u8; 32] {
+        use bitcoin::hashes::{sha256, Hash, HashEngine};
+
+        let mut engine = sha256::Hash::engine();
+        let result = self.try_for_each_decoded_chunk(|chunk| {
+            engine.input(chunk);
+            Ok::<_, core::convert::Infallible>(())
+        });
+        match result {
+            Ok(()) => (),
+            Err(never) => match never {},
+        }
+        sha256::Hash::from_engine(engine).to_byte_array()
+    }
+
+    /// Returns `true` if the decoded value is empty.
+    pub fn is_empty(&self) -> bool {
+        match &self.0 {
+            ParamInner::EncodedBorrowed(_, encoded) => encoded.is_empty(),
+            ParamInner::UnencodedBytes(bytes) => bytes.is_empty(),
+            ParamInner::UnencodedString(string) => string.is_empty(),
+        }
+    }
+
+    /// Compares the decoded values of two parameters without exiting early on a difference.
+    ///
+    /// Both values are first decoded into buffers and then compared byte-by-byte in a loop that
+    /// doesn't branch on the contents of the bytes, only on the lengths. This is slower than
+    /// `PartialEq`, which returns as soon as it finds a difference, so it should only be used for
+    /// security-sensitive data, such as authentication tokens.
+    ///
+    /// **Important:** the percent-decoding itself is **not** constant-time - its timing depends
+    /// on the positions of escape sequences in the encoded values. The lengths of the values are
+    /// not hidden either.
+    pub fn decoded_bytes_equal(a: &Param<'_>, b: &Param<'_>) -> bool {
+        let a = a.decoded_bytes().collect::<alloc::vec::Vec<_>>();
+        let b = b.decoded_bytes().collect::<alloc::vec::Vec<_>>();
+        let mut difference = (a.len() != b.len()) as u8;
+        for i in 0..a.len().max(b.len()) {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            difference |= x ^ y;
+        }
+        difference == 0
     }
 
     /// Creates a byte iterator yielding decoded bytes.
@@ -154,7 +609,7 @@ impl<'a> Param<'a> {
     #[cfg_attr(docsrs, doc(cfg(feature = "non-compliant-bytes")))]
     pub fn bytes(&self) -> ParamBytes<'_> {
         ParamBytes(match &self.0 {
-            ParamInner::EncodedBorrowed(decoder) => Either::Left(decoder.clone()),
+            ParamInner::EncodedBorrowed(decoder, encoded) => Either::Left(BytesDecoder::new(decoder, encoded)),
             ParamInner::UnencodedBytes(bytes) => Either::Right(bytes.iter().cloned()),
             ParamInner::UnencodedString(string) => Either::Right(string.as_bytes().iter().cloned()),
         })
@@ -165,7 +620,7 @@ impl<'a> Param<'a> {
     #[cfg_attr(docsrs, doc(cfg(feature = "non-compliant-bytes")))]
     pub fn into_bytes(self) -> ParamBytesOwned<'a> {
         ParamBytesOwned(match self.0 {
-            ParamInner::EncodedBorrowed(decoder) => Either::Left(decoder),
+            ParamInner::EncodedBorrowed(decoder, encoded) => Either::Left(BytesDecoder::new(&decoder, encoded)),
             ParamInner::UnencodedBytes(Cow::Borrowed(bytes)) => Either::Right(Either::Left(bytes.iter().cloned())),
             ParamInner::UnencodedBytes(Cow::Owned(bytes)) => Either::Right(Either::Right(bytes.into_iter())),
             ParamInner::UnencodedString(Cow::Borrowed(string)) => Either::Right(Either::Left(string.as_bytes().iter().cloned())),
@@ -173,10 +628,171 @@ impl<'a> Param<'a> {
         })
     }
 
+    /// Decodes the parameter as UTF-8 string without consuming it.
+    ///
+    /// This is the same as converting a reborrowed parameter into `Cow<str>`, so it only
+    /// allocates if percent-decoding is needed.
+    pub fn try_decode_utf8(&self) -> Result<Cow<'_, str>, core::str::Utf8Error> {
+        self.reborrow().try_into()
+    }
+
+    /// Decodes the parameter as UTF-8 string replacing invalid sequences.
+    ///
+    /// This has the same semantics as [`String::from_utf8_lossy`] - invalid sequences are
+    /// replaced with `U+FFFD REPLACEMENT CHARACTER`. Useful when the value is only displayed.
+    ///
+    /// ```
+    /// let uri: bip21::Uri<'_, _> = bip21::Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Caf%E9").unwrap();
+    /// assert_eq!(uri.label.unwrap().decode_utf8_lossy(), "Caf\u{FFFD}");
+    /// ```
+    pub fn decode_utf8_lossy(&self) -> Cow<'_, str> {
+        match self.try_decode_utf8() {
+            Ok(string) => string,
+            Err(_) => {
+                let bytes = self.decoded_bytes().collect::<alloc::vec::Vec<_>>();
+                Cow::Owned(String::from_utf8_lossy(&bytes).into_owned())
+            },
+        }
+    }
+
+    /// Converts the parameter into `String` replacing invalid UTF-8 sequences.
+    ///
+    /// This is the consuming version of [`decode_utf8_lossy`](Self::decode_utf8_lossy) which
+    /// reuses the allocation of owned values. It never fails so it's convenient for displaying
+    /// labels and messages, which are required to be UTF-8 by BIP21 anyway.
+    pub fn into_string_lossy(self) -> String {
+        match self.0 {
+            ParamInner::UnencodedString(string) => string.into_owned(),
+            ParamInner::UnencodedBytes(Cow::Owned(bytes)) => match String::from_utf8(bytes) {
+                Ok(string) => string,
+                Err(error) => String::from_utf8_lossy(error.as_bytes()).into_owned(),
+            },
+            inner => Param(inner).decode_utf8_lossy().into_owned(),
+        }
+    }
+
+    /// Splits the decoded value on the first occurrence of `delimiter`.
+    ///
+    /// This is useful for parsing structured values of extension parameters. The delimiter is
+    /// matched in the decoded value, so it may be percent-encoded in the URI. The parts borrow
+    /// from `self` unless percent-decoding is required.
+    ///
+    /// Returns `None` if the delimiter is not found.
+    pub fn split_once(&self, delimiter: char) -> Option<(Param<'_>, Param<'_>)> {
+        fn split_bytes(bytes: &[u8], delimiter: &[u8]) -> Option<(usize, usize)> {
+            let pos = bytes.windows(delimiter.len()).position(|window| window == delimiter)?;
+            Some((pos, pos + delimiter.len()))
+        }
+
+        let mut buf = [0; 4];
+        let delimiter_str = &*delimiter.encode_utf8(&mut buf);
+        let unencoded: &str = match &self.0 {
+            // No percent-encoding means the decoded value is the same as the encoded one.
+            ParamInner::EncodedBorrowed(_, encoded) if !encoded.contains('%') => encoded,
+            ParamInner::EncodedBorrowed(decoder, _) => {
+                let mut first = decoder.clone().collect::<alloc::vec::Vec<u8>>();
+                let (end, start) = split_bytes(&first, delimiter_str.as_bytes())?;
+                let second = first.split_off(start);
+                first.truncate(end);
+                return Some((
+                    Param(ParamInner::UnencodedBytes(Cow::Owned(first))),
+                    Param(ParamInner::UnencodedBytes(Cow::Owned(second))),
+                ));
+            },
+            ParamInner::UnencodedBytes(bytes) => {
+                let (end, start) = split_bytes(bytes, delimiter_str.as_bytes())?;
+                let first = Param(ParamInner::UnencodedBytes(Cow::Borrowed(&bytes[..end])));
+                let second = Param(ParamInner::UnencodedBytes(Cow::Borrowed(&bytes[start..])));
+                return Some((first, second));
+            },
+            ParamInner::UnencodedString(string) => string,
+        };
+        let pos = unencoded.find(delimiter)?;
+        let first = Param(ParamInner::UnencodedString(Cow::Borrowed(&unencoded[..pos])));
+        let second = Param(ParamInner::UnencodedString(Cow::Borrowed(&unencoded[(pos + delimiter_str.len())..])));
+        Some((first, second))
+    }
+
+    /// Truncates the decoded value to at most `max_bytes` bytes.
+    ///
+    /// The value is cut at the last UTF-8 character boundary at or before `max_bytes` so a
+    /// multi-byte character is never split. Only up to `max_bytes + 1` bytes are decoded which
+    /// makes this suitable for previews of long labels and messages.
+    pub fn truncate_decoded(self, max_bytes: usize) -> Param<'static> {
+        let mut bytes = self.decoded_bytes().take(max_bytes.saturating_add(1)).collect::<alloc::vec::Vec<u8>>();
+        if bytes.len() > max_bytes {
+            let mut end = max_bytes;
+            while end > 0 && is_utf8_continuation(bytes[end]) {
+                end -= 1;
+            }
+            bytes.truncate(end);
+        }
+        Param(ParamInner::UnencodedBytes(Cow::Owned(bytes)))
+    }
+
+    /// Truncates the decoded value to at most `max_chars` characters.
+    ///
+    /// This is the same as [`truncate_decoded`](Self::truncate_decoded) except the limit is
+    /// in characters rather than bytes.
+    pub fn truncate_decoded_chars(self, max_chars: usize) -> Param<'static> {
+        let mut chars = 0;
+        let bytes = self
+            .decoded_bytes()
+            .take_while(|byte| {
+                if !is_utf8_continuation(*byte) {
+                    chars += 1;
+                }
+                chars <= max_chars
+            })
+            .collect::<alloc::vec::Vec<u8>>();
+        Param(ParamInner::UnencodedBytes(Cow::Owned(bytes)))
+    }
+
+    /// Returns the decoded value as a string slice with the original lifetime.
+    ///
+    /// Unlike the conversions into `Cow<'a, str>` this doesn't consume the parameter and the
+    /// returned slice is not tied to the lifetime of `&self`, so it can be stored in the
+    /// deserialization state of extras.
+    ///
+    /// Returns `None` if the value is owned, requires percent-decoding or is not valid UTF-8.
+    pub fn try_as_borrowed_str(&self) -> Option<&'a str> {
+        match &self.0 {
+            ParamInner::EncodedBorrowed(_, encoded) if encoded.contains('%') => None,
+            ParamInner::EncodedBorrowed(_, encoded) => Some(encoded),
+            ParamInner::UnencodedString(Cow::Borrowed(value)) => Some(value),
+            ParamInner::UnencodedBytes(Cow::Borrowed(value)) => core::str::from_utf8(value).ok(),
+            ParamInner::UnencodedString(Cow::Owned(_)) | ParamInner::UnencodedBytes(Cow::Owned(_)) => None,
+        }
+    }
+
+    /// Converts the parameter to owned, making the lifetime `'static`.
+    ///
+    /// Encoded parameters are eagerly decoded, so this may allocate. This is useful for storing
+    /// parameters in long-lived data structures, e.g. `uri.label.map(Param::into_static)`.
+    ///
+    /// The returned parameter no longer remembers how it was encoded in the input so
+    /// [`into_encoded_string`](Param::into_encoded_string) returns the canonical encoding, same as
+    /// displaying it. This is useful before comparing encoded URIs.
+    ///
+    /// Note that this can not be provided as `From<Param<'a>> for Param<'static>` because it would
+    /// conflict with the blanket `From<T> for T` implementation.
+    pub fn into_static(self) -> Param<'static> {
+        self.decode_into_owned()
+    }
+
+    /// Borrows the parameter without copying the data.
+    fn reborrow(&self) -> Param<'_> {
+        match &self.0 {
+            ParamInner::EncodedBorrowed(decoder, encoded) => Param(ParamInner::EncodedBorrowed(decoder.clone(), encoded)),
+            ParamInner::UnencodedBytes(bytes) => Param(ParamInner::UnencodedBytes(Cow::Borrowed(bytes))),
+            ParamInner::UnencodedString(string) => Param(ParamInner::UnencodedString(Cow::Borrowed(string))),
+        }
+    }
+
     /// Decodes the param if encoded making the lifetime static.
     fn decode_into_owned<'b>(self) -> Param<'b> {
         let owned = match self.0 {
-            ParamInner::EncodedBorrowed(decoder) => ParamInner::UnencodedBytes(decoder.collect()),
+            ParamInner::EncodedBorrowed(decoder, _) => ParamInner::UnencodedBytes(decoder.collect()),
             ParamInner::UnencodedString(Cow::Borrowed(value)) => ParamInner::UnencodedString(Cow::Owned(value.to_owned())),
             ParamInner::UnencodedString(Cow::Owned(value)) => ParamInner::UnencodedString(Cow::Owned(value)),
             ParamInner::UnencodedBytes(Cow::Borrowed(value)) => ParamInner::UnencodedBytes(Cow::Owned(value.to_owned())),
@@ -186,13 +802,130 @@ impl<'a> Param<'a> {
     }
 }
 
+/// Returns `true` if the byte is not the first byte of UTF-8 encoded character.
+fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Returns an empty parameter.
+impl<'a> Default for Param<'a> {
+    fn default() -> Self {
+        Param::empty()
+    }
+}
+
 /// Cheap conversion
+///
+/// The string is treated as **unencoded** - `"%20"` stays `"%20"` after decoding. Use
+/// [`PreEncoded`] or `FromStr` to create a parameter from an encoded string.
 impl<'a> From<&'a str> for Param<'a> {
     fn from(value: &'a str) -> Self {
         Param(ParamInner::UnencodedString(Cow::Borrowed(value)))
     }
 }
 
+/// Cheap conversion
+///
+/// Same as `From<&str>`, the string is treated as **unencoded**.
+impl<'a> From<&'a String> for Param<'a> {
+    #[inline]
+    fn from(value: &'a String) -> Self {
+        Param::from(value.as_str())
+    }
+}
+
+/// Cheap conversion
+///
+/// Same as `From<&str>`, the string is treated as **unencoded**.
+impl<'a, 'b> From<&'a Cow<'b, str>> for Param<'a> {
+    #[inline]
+    fn from(value: &'a Cow<'b, str>) -> Self {
+        Param::from(&**value)
+    }
+}
+
+/// Builds a [`Param`] from formatted text.
+///
+/// This is useful when the value is written in multiple steps, [`Param::from_fmt_args`] is
+/// simpler otherwise.
+///
+/// ```
+/// use core::fmt::Write;
+///
+/// let mut writer = bip21::ParamWriter::new();
+/// write!(writer, "Invoice #{}", 42).unwrap();
+/// write!(writer, " for {} BTC", 0.5).unwrap();
+/// assert_eq!(writer.into_param(), "Invoice #42 for 0.5 BTC");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ParamWriter(String);
+
+impl ParamWriter {
+    /// Creates an empty writer.
+    pub fn new() -> Self {
+        ParamWriter(String::new())
+    }
+
+    /// Returns the parameter containing all written text.
+    pub fn into_param(self) -> Param<'static> {
+        self.0.into()
+    }
+}
+
+impl core::fmt::Write for ParamWriter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0.push_str(s);
+        Ok(())
+    }
+}
+
+/// String that is already percent-encoded.
+///
+/// While `From<&str>` for [`Param`] treats the string literally, this type marks the string as
+/// being encoded so it's decoded lazily when needed, the same way as parsed parameters are.
+/// The encoding is validated when constructing this type.
+#[derive(Debug, Clone)]
+pub struct PreEncoded<'a>(PercentDecode<'a>, &'a str);
+
+impl<'a> PreEncoded<'a> {
+    /// Validates the percent-encoding of the string.
+    pub fn new(encoded: &'a str) -> Result<Self, PercentDecodeError> {
+        Ok(PreEncoded(percent_encoding_rfc3986::percent_decode_str(encoded)?, encoded))
+    }
+
+    /// Returns the encoded string.
+    pub fn as_str(&self) -> &'a str {
+        self.1
+    }
+}
+
+impl<'a> TryFrom<&'a str> for PreEncoded<'a> {
+    type Error = PercentDecodeError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        PreEncoded::new(value)
+    }
+}
+
+/// Percent-decodes the string into an owned parameter.
+///
+/// Unlike `From<&str>` the string is treated as **encoded** - `"%20"` becomes `" "`. Use
+/// [`PreEncoded`] to avoid the allocation.
+impl core::str::FromStr for Param<'static> {
+    type Err = PercentDecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PreEncoded::new(s).map(|encoded| Param::from(encoded).into_static())
+    }
+}
+
+/// Cheap conversion
+impl<'a> From<PreEncoded<'a>> for Param<'a> {
+    fn from(value: PreEncoded<'a>) -> Self {
+        Param(ParamInner::EncodedBorrowed(value.0, value.1))
+    }
+}
+
 /// Cheap conversion
 impl<'a> From<String> for Param<'a> {
     fn from(value: String) -> Self {
@@ -224,7 +957,7 @@ impl<'a> From<Vec<u8>> for Param<'a> {
 impl<'a> From<Param<'a>> for Vec<u8> {
     fn from(value: Param<'a>) -> Self {
         match value.0 {
-            ParamInner::EncodedBorrowed(decoder) => decoder.collect(),
+            ParamInner::EncodedBorrowed(decoder, _) => decoder.collect(),
             ParamInner::UnencodedString(Cow::Borrowed(value)) => value.as_bytes().to_owned(),
             ParamInner::UnencodedString(Cow::Owned(value)) => value.into(),
             ParamInner::UnencodedBytes(value) => value.into(),
@@ -238,7 +971,7 @@ impl<'a> From<Param<'a>> for Vec<u8> {
 impl<'a> From<Param<'a>> for Cow<'a, [u8]> {
     fn from(value: Param<'a>) -> Self {
         match value.0 {
-            ParamInner::EncodedBorrowed(decoder) => decoder.into(),
+            ParamInner::EncodedBorrowed(decoder, _) => decoder.into(),
             ParamInner::UnencodedString(Cow::Borrowed(value)) => Cow::Borrowed(value.as_bytes()),
             ParamInner::UnencodedString(Cow::Owned(value)) => Cow::Owned(value.into()),
             ParamInner::UnencodedBytes(value) => value,
@@ -251,7 +984,7 @@ impl<'a> TryFrom<Param<'a>> for String {
 
     fn try_from(value: Param<'a>) -> Result<Self, Self::Error> {
         match value.0 {
-            ParamInner::EncodedBorrowed(decoder) => <Cow<'_, str>>::try_from(decoder).map(Into::into),
+            ParamInner::EncodedBorrowed(decoder, _) => <Cow<'_, str>>::try_from(decoder).map(Into::into),
             ParamInner::UnencodedString(value) => Ok(value.into()),
             ParamInner::UnencodedBytes(Cow::Borrowed(value)) => Ok(core::str::from_utf8(value)?.to_owned()),
             ParamInner::UnencodedBytes(Cow::Owned(value)) => String::from_utf8(value).map_err(|error| error.utf8_error()),
@@ -259,12 +992,25 @@ impl<'a> TryFrom<Param<'a>> for String {
     }
 }
 
+/// Zero-copy conversion
+///
+/// This succeeds under the same conditions as [`Param::try_as_borrowed_str`] - if the value is
+/// borrowed, valid UTF-8 and doesn't need percent-decoding. The parameter is returned back in the
+/// error otherwise.
+impl<'a> TryFrom<Param<'a>> for &'a str {
+    type Error = Param<'a>;
+
+    fn try_from(value: Param<'a>) -> Result<Self, Self::Error> {
+        value.try_as_borrowed_str().ok_or(value)
+    }
+}
+
 impl<'a> TryFrom<Param<'a>> for Cow<'a, str> {
     type Error = core::str::Utf8Error;
 
     fn try_from(value: Param<'a>) -> Result<Self, Self::Error> {
         match value.0 {
-            ParamInner::EncodedBorrowed(decoder) => decoder.try_into(),
+            ParamInner::EncodedBorrowed(decoder, _) => decoder.try_into(),
             ParamInner::UnencodedString(value) => Ok(value),
             ParamInner::UnencodedBytes(Cow::Borrowed(value)) => Ok(Cow::Borrowed(core::str::from_utf8(value)?)),
             ParamInner::UnencodedBytes(Cow::Owned(value)) => Ok(Cow::Owned(String::from_utf8(value).map_err(|error| error.utf8_error())?)),
@@ -272,159 +1018,2633 @@ impl<'a> TryFrom<Param<'a>> for Cow<'a, str> {
     }
 }
 
-#[derive(Debug, Clone)]
-enum ParamInner<'a> {
-    EncodedBorrowed(PercentDecode<'a>),
-    UnencodedBytes(Cow<'a, [u8]>),
-    UnencodedString(Cow<'a, str>),
+/// Parses the amount denominated in bitcoins, the same way as the `amount` parameter.
+impl<'a> TryFrom<Param<'a>> for bitcoin::Amount {
+    type Error = ParamAmountError;
+
+    fn try_from(value: Param<'a>) -> Result<Self, Self::Error> {
+        let string = <Cow<'_, str>>::try_from(value).map_err(ParamAmountErrorInner::Utf8)?;
+        let amount = bitcoin::Amount::from_str_in(&string, bitcoin::Denomination::Bitcoin).map_err(ParamAmountErrorInner::Amount)?;
+        Ok(amount)
+    }
 }
 
-/// Iterator over decoded bytes inside paramter.
-///
-/// The lifetime of this may be shorter than that of [`Param<'a>`].
-#[cfg(feature = "non-compliant-bytes")]
-#[cfg_attr(docsrs, doc(cfg(feature = "non-compliant-bytes")))]
-pub struct ParamBytes<'a>(ParamIterInner<'a, core::iter::Cloned<core::slice::Iter<'a, u8>>>);
+/// Formats the amount in bitcoins, the same way as the `amount` parameter.
+impl<'a> From<bitcoin::Amount> for Param<'a> {
+    fn from(value: bitcoin::Amount) -> Self {
+        use alloc::string::ToString;
 
-/// Iterator over decoded bytes inside paramter.
-///
-/// The lifetime of this is same as that of [`Param<'a>`].
-#[cfg(feature = "non-compliant-bytes")]
-#[cfg_attr(docsrs, doc(cfg(feature = "non-compliant-bytes")))]
-pub struct ParamBytesOwned<'a>(ParamIterInner<'a, Either<core::iter::Cloned<core::slice::Iter<'a, u8>>, alloc::vec::IntoIter<u8>>>);
+        value.display_in(bitcoin::Denomination::Bitcoin).to_string().into()
+    }
+}
 
-#[cfg(feature = "non-compliant-bytes")]
-type ParamIterInner<'a, T> = either::Either<PercentDecode<'a>, T>;
+/// Error returned when converting [`Param`] to [`bitcoin::Amount`] fails.
+#[derive(Debug, Clone)]
+pub struct ParamAmountError(ParamAmountErrorInner);
 
-/// Empty extras.
-///
+#[derive(Debug, Clone)]
+enum ParamAmountErrorInner {
+    Utf8(core::str::Utf8Error),
+    Amount(bitcoin::amount::ParseAmountError),
+}
+
+impl From<ParamAmountErrorInner> for ParamAmountError {
+    fn from(value: ParamAmountErrorInner) -> Self {
+        ParamAmountError(value)
+    }
+}
+
+impl core::fmt::Display for ParamAmountError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.0 {
+            #[cfg(feature = "std")]
+            ParamAmountErrorInner::Utf8(_) => write!(f, "the amount is not valid UTF-8"),
+            #[cfg(not(feature = "std"))]
+            ParamAmountErrorInner::Utf8(error) => write!(f, "the amount is not valid UTF-8: {}", error),
+            #[cfg(feature = "std")]
+            ParamAmountErrorInner::Amount(_) => write!(f, "the amount is invalid"),
+            #[cfg(not(feature = "std"))]
+            ParamAmountErrorInner::Amount(error) => write!(f, "the amount is invalid: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for ParamAmountError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.0 {
+            ParamAmountErrorInner::Utf8(error) => Some(error),
+            ParamAmountErrorInner::Amount(error) => Some(error),
+        }
+    }
+}
+
+impl<'a> Param<'a> {
+    /// Parses the decoded value using `FromStr`.
+    ///
+    /// This is a generic version of the numeric `TryFrom` conversions and it's useful when
+    /// implementing extras with values of other types.
+    pub fn parse<T: core::str::FromStr>(&self) -> Result<T, ParamParseError<T::Err>> {
+        let string = self.try_decode_utf8().map_err(ParamParseErrorInner::Utf8)?;
+        let value = string.parse().map_err(ParamParseErrorInner::Parse)?;
+        Ok(value)
+    }
+}
+
+/// URL-safe base64 engine that doesn't emit padding but accepts it.
+#[cfg(feature = "base64")]
+const BASE64_URL: base64::engine::GeneralPurpose = base64::engine::GeneralPurpose::new(
+    &base64::alphabet::URL_SAFE,
+    base64::engine::GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent),
+);
+
+#[cfg(feature = "base64")]
+#[cfg_attr(docsrs, doc(cfg(feature = "base64")))]
+impl<'a> Param<'a> {
+    /// Decodes base64url-encoded binary data into a parameter containing raw bytes.
+    ///
+    /// Padding is optional. Note that the bytes are not required to be valid UTF-8 so the
+    /// parameter may not be BIP21-compliant, hence this requires `non-compliant-bytes` as well.
+    #[cfg(feature = "non-compliant-bytes")]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "base64", feature = "non-compliant-bytes"))))]
+    pub fn from_base64(b64: &str) -> Result<Param<'static>, base64::DecodeError> {
+        use base64::Engine;
+
+        BASE64_URL.decode(b64).map(Param::from)
+    }
+
+    /// Encodes the decoded bytes of the parameter using unpadded base64url.
+    pub fn to_base64(&self) -> String {
+        use base64::Engine;
+
+        match &self.0 {
+            ParamInner::UnencodedBytes(bytes) => BASE64_URL.encode(bytes),
+            ParamInner::UnencodedString(string) => BASE64_URL.encode(string.as_bytes()),
+            ParamInner::EncodedBorrowed(decoder, _) => BASE64_URL.encode(decoder.clone().collect::<alloc::vec::Vec<u8>>()),
+        }
+    }
+}
+
+macro_rules! impl_param_int_conversions {
+    ($($int:ty),*) => {
+        $(
+            /// Parses the decoded value as a decimal number.
+            impl<'a> TryFrom<Param<'a>> for $int {
+                type Error = ParamParseError<core::num::ParseIntError>;
+
+                fn try_from(value: Param<'a>) -> Result<Self, Self::Error> {
+                    value.parse()
+                }
+            }
+
+            /// Formats the number as decimal.
+            impl<'a> From<$int> for Param<'a> {
+                fn from(value: $int) -> Self {
+                    use alloc::string::ToString;
+
+                    value.to_string().into()
+                }
+            }
+        )*
+    };
+}
+
+impl_param_int_conversions!(u64, i64, u32);
+
+/// Error returned when parsing [`Param`] fails.
+///
+/// `E` is the error returned by the `FromStr` implementation of the target type.
+#[derive(Debug, Clone)]
+pub struct ParamParseError<E>(ParamParseErrorInner<E>);
+
+#[derive(Debug, Clone)]
+enum ParamParseErrorInner<E> {
+    Utf8(core::str::Utf8Error),
+    Parse(E),
+}
+
+impl<E> From<ParamParseErrorInner<E>> for ParamParseError<E> {
+    fn from(value: ParamParseErrorInner<E>) -> Self {
+        ParamParseError(value)
+    }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for ParamParseError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.0 {
+            #[cfg(feature = "std")]
+            ParamParseErrorInner::Utf8(_) => write!(f, "the parameter is not valid UTF-8"),
+            #[cfg(not(feature = "std"))]
+            ParamParseErrorInner::Utf8(error) => write!(f, "the parameter is not valid UTF-8: {}", error),
+            #[cfg(feature = "std")]
+            ParamParseErrorInner::Parse(_) => write!(f, "failed to parse the parameter"),
+            #[cfg(not(feature = "std"))]
+            ParamParseErrorInner::Parse(error) => write!(f, "failed to parse the parameter: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl<E: std::error::Error + 'static> std::error::Error for ParamParseError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.0 {
+            ParamParseErrorInner::Utf8(error) => Some(error),
+            ParamParseErrorInner::Parse(error) => Some(error),
+        }
+    }
+}
+
+/// Decodes the parameter into a fixed-capacity string without allocating.
+///
+/// Fails if the decoded value is not valid UTF-8 or is longer than `N` bytes.
+#[cfg(feature = "heapless")]
+#[cfg_attr(docsrs, doc(cfg(feature = "heapless")))]
+impl<'a, const N: usize> TryFrom<Param<'a>> for heapless::String<N> {
+    type Error = ParamCapacityError;
+
+    fn try_from(value: Param<'a>) -> Result<Self, Self::Error> {
+        let mut bytes = heapless::Vec::<u8, N>::new();
+        for byte in value.decoded_bytes() {
+            bytes.push(byte).map_err(|_| ParamCapacityErrorInner::Capacity(N))?;
+        }
+        let string = core::str::from_utf8(&bytes).map_err(ParamCapacityErrorInner::Utf8)?;
+        let mut result = heapless::String::new();
+        result.push_str(string).expect("the length was already checked");
+        Ok(result)
+    }
+}
+
+/// Copies the string into the parameter.
+#[cfg(feature = "heapless")]
+#[cfg_attr(docsrs, doc(cfg(feature = "heapless")))]
+impl<'a, const N: usize> From<heapless::String<N>> for Param<'a> {
+    fn from(value: heapless::String<N>) -> Self {
+        Param(ParamInner::UnencodedString(Cow::Owned(value.as_str().to_owned())))
+    }
+}
+
+/// Copies the bytes into the parameter.
+#[cfg(all(feature = "heapless", feature = "non-compliant-bytes"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "heapless", feature = "non-compliant-bytes"))))]
+impl<'a, const N: usize> From<heapless::Vec<u8, N>> for Param<'a> {
+    fn from(value: heapless::Vec<u8, N>) -> Self {
+        Param(ParamInner::UnencodedBytes(Cow::Owned(value.to_vec())))
+    }
+}
+
+/// Error returned when converting [`Param`] to [`heapless::String`] fails.
+#[cfg(feature = "heapless")]
+#[cfg_attr(docsrs, doc(cfg(feature = "heapless")))]
+#[derive(Debug, Clone)]
+pub struct ParamCapacityError(ParamCapacityErrorInner);
+
+#[cfg(feature = "heapless")]
+#[derive(Debug, Clone)]
+enum ParamCapacityErrorInner {
+    Utf8(core::str::Utf8Error),
+    Capacity(usize),
+}
+
+#[cfg(feature = "heapless")]
+impl From<ParamCapacityErrorInner> for ParamCapacityError {
+    fn from(value: ParamCapacityErrorInner) -> Self {
+        ParamCapacityError(value)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl core::fmt::Display for ParamCapacityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.0 {
+            #[cfg(feature = "std")]
+            ParamCapacityErrorInner::Utf8(_) => write!(f, "the parameter is not valid UTF-8"),
+            #[cfg(not(feature = "std"))]
+            ParamCapacityErrorInner::Utf8(error) => write!(f, "the parameter is not valid UTF-8: {}", error),
+            ParamCapacityErrorInner::Capacity(capacity) => write!(f, "the parameter is longer than {} bytes", capacity),
+        }
+    }
+}
+
+#[cfg(all(feature = "heapless", feature = "std"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "heapless", feature = "std"))))]
+impl std::error::Error for ParamCapacityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.0 {
+            ParamCapacityErrorInner::Utf8(error) => Some(error),
+            ParamCapacityErrorInner::Capacity(_) => None,
+        }
+    }
+}
+
+/// Compares decoded values.
+impl<'a, 'b> PartialEq<Param<'b>> for Param<'a> {
+    fn eq(&self, other: &Param<'b>) -> bool {
+        self.decoded_bytes().eq(other.decoded_bytes())
+    }
+}
+
+impl<'a> Eq for Param<'a> {}
+
+/// Compares decoded values lexicographically by bytes.
+impl<'a, 'b> PartialOrd<Param<'b>> for Param<'a> {
+    fn partial_cmp(&self, other: &Param<'b>) -> Option<core::cmp::Ordering> {
+        Some(self.decoded_bytes().cmp(other.decoded_bytes()))
+    }
+}
+
+/// Compares decoded values lexicographically by bytes.
+impl<'a> Ord for Param<'a> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.decoded_bytes().cmp(other.decoded_bytes())
+    }
+}
+
+/// Hashes decoded value.
+impl<'a> core::hash::Hash for Param<'a> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        for byte in self.decoded_bytes() {
+            state.write_u8(byte);
+        }
+        // Same as str to avoid prefix collisions.
+        state.write_u8(0xff);
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a> PartialEq<str> for Param<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.decoded_bytes().eq(other.bytes())
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a, 'b> PartialEq<&'b str> for Param<'a> {
+    fn eq(&self, other: &&'b str) -> bool {
+        *self == **other
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a> PartialEq<String> for Param<'a> {
+    fn eq(&self, other: &String) -> bool {
+        *self == **other
+    }
+}
+
+/// Serializes the decoded value as a string.
+///
+/// Serialization fails if the decoded value is not valid UTF-8, unless `non-compliant-bytes`
+/// is enabled, in which case such values are serialized as bytes.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'a> serde::Serialize for Param<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.try_decode_utf8() {
+            Ok(string) => serializer.serialize_str(&string),
+            #[cfg(feature = "non-compliant-bytes")]
+            Err(_) => serializer.serialize_bytes(&self.bytes().collect::<Vec<u8>>()),
+            #[cfg(not(feature = "non-compliant-bytes"))]
+            Err(error) => Err(serde::ser::Error::custom(error)),
+        }
+    }
+}
+
+/// Deserializes an unencoded string.
+///
+/// Bytes are accepted as well if `non-compliant-bytes` is enabled.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> serde::Deserialize<'de> for Param<'static> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Param<'static>;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                #[cfg(feature = "non-compliant-bytes")]
+                {
+                    f.write_str("a string or bytes")
+                }
+                #[cfg(not(feature = "non-compliant-bytes"))]
+                {
+                    f.write_str("a string")
+                }
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(Param::from(String::from(value)))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, value: String) -> Result<Self::Value, E> {
+                Ok(Param::from(value))
+            }
+
+            #[cfg(feature = "non-compliant-bytes")]
+            fn visit_bytes<E: serde::de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+                Ok(Param::from(value.to_vec()))
+            }
+
+            #[cfg(feature = "non-compliant-bytes")]
+            fn visit_byte_buf<E: serde::de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(Param::from(value))
+            }
+        }
+
+        deserializer.deserialize_string(Visitor)
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a> PartialEq<Param<'a>> for str {
+    fn eq(&self, other: &Param<'a>) -> bool {
+        *other == *self
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a> PartialEq<Param<'a>> for &str {
+    fn eq(&self, other: &Param<'a>) -> bool {
+        *other == **self
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a> PartialEq<Param<'a>> for String {
+    fn eq(&self, other: &Param<'a>) -> bool {
+        *other == **self
+    }
+}
+
+/// Parameter guaranteed to have a non-empty decoded value.
+///
+/// This is useful in extras that require a parameter to be present and non-empty. The parameter
+/// is accessible via `Deref`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct NonEmptyParam<'a>(Param<'a>);
+
+impl<'a> NonEmptyParam<'a> {
+    /// Returns the inner parameter.
+    pub fn into_inner(self) -> Param<'a> {
+        self.0
+    }
+}
+
+/// Fails if the decoded value is empty, returning the parameter back.
+impl<'a> TryFrom<Param<'a>> for NonEmptyParam<'a> {
+    type Error = Param<'a>;
+
+    fn try_from(value: Param<'a>) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            Err(value)
+        } else {
+            Ok(NonEmptyParam(value))
+        }
+    }
+}
+
+impl<'a> From<NonEmptyParam<'a>> for Param<'a> {
+    fn from(value: NonEmptyParam<'a>) -> Self {
+        value.0
+    }
+}
+
+impl<'a> core::ops::Deref for NonEmptyParam<'a> {
+    type Target = Param<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a> AsRef<Param<'a>> for NonEmptyParam<'a> {
+    fn as_ref(&self) -> &Param<'a> {
+        &self.0
+    }
+}
+
+/// Compares decoded values.
+impl<'a, 'b> PartialEq<Param<'b>> for NonEmptyParam<'a> {
+    fn eq(&self, other: &Param<'b>) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a> PartialEq<str> for NonEmptyParam<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a, 'b> PartialEq<&'b str> for NonEmptyParam<'a> {
+    fn eq(&self, other: &&'b str) -> bool {
+        self.0 == **other
+    }
+}
+
+/// Compares decoded value with the string.
+impl<'a> PartialEq<String> for NonEmptyParam<'a> {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == **other
+    }
+}
+
+impl<'a> TryFrom<NonEmptyParam<'a>> for String {
+    type Error = core::str::Utf8Error;
+
+    fn try_from(value: NonEmptyParam<'a>) -> Result<Self, Self::Error> {
+        value.0.try_into()
+    }
+}
+
+impl<'a> TryFrom<NonEmptyParam<'a>> for Cow<'a, str> {
+    type Error = core::str::Utf8Error;
+
+    fn try_from(value: NonEmptyParam<'a>) -> Result<Self, Self::Error> {
+        value.0.try_into()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ParamInner<'a> {
+    /// The decoder and the original encoded string it was created from.
+    EncodedBorrowed(PercentDecode<'a>, &'a str),
+    UnencodedBytes(Cow<'a, [u8]>),
+    UnencodedString(Cow<'a, str>),
+}
+
+/// Iterator over decoded bytes inside paramter.
+///
+/// The lifetime of this may be shorter than that of [`Param<'a>`].
+#[cfg(feature = "non-compliant-bytes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "non-compliant-bytes")))]
+pub struct ParamBytes<'a>(ParamIterInner<'a, core::iter::Cloned<core::slice::Iter<'a, u8>>>);
+
+/// Iterator over decoded bytes inside paramter.
+///
+/// The lifetime of this is same as that of [`Param<'a>`].
+#[cfg(feature = "non-compliant-bytes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "non-compliant-bytes")))]
+pub struct ParamBytesOwned<'a>(ParamIterInner<'a, Either<core::iter::Cloned<core::slice::Iter<'a, u8>>, alloc::vec::IntoIter<u8>>>);
+
+#[cfg(feature = "non-compliant-bytes")]
+type ParamIterInner<'a, T> = either::Either<BytesDecoder<'a>, T>;
+
+/// Decodes percent-encoded bytes from both ends.
+///
+/// `PercentDecode` can only decode forward, this works because `%` always starts an encoded
+/// triplet in a valid input so the input can be decoded from the back as well.
+#[cfg(feature = "non-compliant-bytes")]
+#[derive(Debug, Clone)]
+struct BytesDecoder<'a> {
+    encoded: &'a [u8],
+    remaining: usize,
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> BytesDecoder<'a> {
+    /// Creates the decoder from `encoded` which was already validated by `decoder`.
+    fn new(decoder: &PercentDecode<'a>, encoded: &'a str) -> Self {
+        BytesDecoder {
+            encoded: encoded.as_bytes(),
+            remaining: decoder.len(),
+        }
+    }
+
+    fn decode_triplet(high: u8, low: u8) -> u8 {
+        fn hex_value(digit: u8) -> u8 {
+            match digit {
+                b'0'..=b'9' => digit - b'0',
+                b'a'..=b'f' => digit - b'a' + 10,
+                b'A'..=b'F' => digit - b'A' + 10,
+                _ => unreachable!("the input was validated"),
+            }
+        }
+
+        hex_value(high) << 4 | hex_value(low)
+    }
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> Iterator for BytesDecoder<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (byte, rest) = match self.encoded {
+            [b'%', high, low, rest @ ..] => (Self::decode_triplet(*high, *low), rest),
+            [byte, rest @ ..] => (*byte, rest),
+            [] => return None,
+        };
+        self.encoded = rest;
+        self.remaining -= 1;
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> DoubleEndedIterator for BytesDecoder<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (byte, rest) = match self.encoded {
+            [rest @ .., b'%', high, low] => (Self::decode_triplet(*high, *low), rest),
+            [rest @ .., byte] => (*byte, rest),
+            [] => return None,
+        };
+        self.encoded = rest;
+        self.remaining -= 1;
+        Some(byte)
+    }
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> ExactSizeIterator for BytesDecoder<'a> {}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> core::iter::FusedIterator for BytesDecoder<'a> {}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> Iterator for ParamBytes<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> DoubleEndedIterator for ParamBytes<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> ExactSizeIterator for ParamBytes<'a> {}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> core::iter::FusedIterator for ParamBytes<'a> {}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> Iterator for ParamBytesOwned<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> DoubleEndedIterator for ParamBytesOwned<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> ExactSizeIterator for ParamBytesOwned<'a> {}
+
+#[cfg(feature = "non-compliant-bytes")]
+impl<'a> core::iter::FusedIterator for ParamBytesOwned<'a> {}
+
+/// Empty extras.
+///
 /// This type can be used if extras are not required.
 /// It is also the default type parameter of [`Uri`].
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct NoExtras;
 
-/// This is a state used to deserialize `NoExtras` - it doesn't expect any parameters.
-#[derive(Debug, Default, Copy, Clone)]
-pub struct EmptyState;
+/// Displays nothing since there are no parameters.
+impl core::fmt::Display for NoExtras {
+    fn fmt(&self, _f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        Ok(())
+    }
+}
+
+/// This is a state used to deserialize `NoExtras` - it doesn't expect any parameters.
+///
+/// See the [`de`] module documentation for how to implement states of custom extras.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct EmptyState;
+
+impl DeserializeParams<'_> for NoExtras {
+    type DeserializationState = EmptyState;
+}
+
+impl DeserializationError for NoExtras {
+    type Error = core::convert::Infallible;
+}
+
+impl<'de> DeserializationState<'de> for EmptyState {
+    type Value = NoExtras;
+
+    fn is_param_known(&self, _key: &str) -> bool {
+        false
+    }
+
+    fn deserialize_temp(&mut self, _key: &str, _value: Param<'_>) -> Result<de::ParamKind, <Self::Value as DeserializationError>::Error> {
+        Ok(de::ParamKind::Unknown)
+    }
+
+    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+        Ok(Default::default())
+    }
+}
+
+impl SerializeParams for NoExtras {
+    type Key = core::convert::Infallible;
+    type Value = core::convert::Infallible;
+    type Iterator = core::iter::Empty<(Self::Key, Self::Value)>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        core::iter::empty()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(0))
+    }
+}
+
+impl SerializeParams for &NoExtras {
+    type Key = core::convert::Infallible;
+    type Value = core::convert::Infallible;
+    type Iterator = core::iter::Empty<(Self::Key, Self::Value)>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        core::iter::empty()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Uri;
+    use alloc::string::ToString;
+    use alloc::borrow::Cow;
+    use core::convert::{TryFrom, TryInto};
+
+    fn check_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        check_send_sync::<crate::de::UriError>();
+        check_send_sync::<crate::de::Error<core::convert::Infallible>>();
+        check_send_sync::<crate::de::ParamKind>();
+        check_send_sync::<crate::merge::MergeError<core::convert::Infallible>>();
+        check_send_sync::<crate::de::Recorded<crate::NoExtras>>();
+        check_send_sync::<crate::de::RecordingState<crate::EmptyState>>();
+        check_send_sync::<crate::Param<'static>>();
+        check_send_sync::<Uri<'static>>();
+        check_send_sync::<Uri<'static, bitcoin::address::NetworkUnchecked>>();
+        check_send_sync::<crate::NoExtras>();
+        check_send_sync::<crate::EmptyState>();
+        #[cfg(feature = "non-compliant-bytes")]
+        check_send_sync::<crate::ParamBytes<'static>>();
+        #[cfg(feature = "non-compliant-bytes")]
+        check_send_sync::<crate::ParamBytesOwned<'static>>();
+    }
+
+    // Note: the official test vectors contained an invalid address so it was replaced with the address of Andreas Antonopoulos.
+
+    #[test]
+    fn just_address() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd";
+        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
+        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert!(uri.amount.is_none());
+        assert!(uri.label.is_none());
+        assert!(uri.message.is_none());
+
+        assert_eq!(uri.to_string(), input);
+    }
+
+    #[test]
+    fn address_with_name() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr";
+        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
+        let label: Cow<'_, str> = uri.label.clone().unwrap().try_into().unwrap();
+        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert_eq!(label, "Luke-Jr");
+        assert!(uri.amount.is_none());
+        assert!(uri.message.is_none());
+
+        assert_eq!(uri.to_string(), input);
+    }
+
+    #[allow(clippy::inconsistent_digit_grouping)] // Use sats/bitcoin when grouping.
+    #[test]
+    fn request_20_point_30_btc_to_luke_dash_jr() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=20.3&label=Luke-Jr";
+        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
+        let label: Cow<'_, str> = uri.label.clone().unwrap().try_into().unwrap();
+        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert_eq!(label, "Luke-Jr");
+        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(20_30_000_000)));
+        assert!(uri.message.is_none());
+
+        assert_eq!(uri.to_string(), input);
+    }
+
+    #[allow(clippy::inconsistent_digit_grouping)] // Use sats/bitcoin when grouping.
+    #[test]
+    fn request_50_btc_with_message() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=50&label=Luke-Jr&message=Donation%20for%20project%20xyz";
+        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
+        let label: Cow<'_, str> = uri.label.clone().unwrap().try_into().unwrap();
+        let message: Cow<'_, str> = uri.message.clone().unwrap().try_into().unwrap();
+        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(50_00_000_000)));
+        assert_eq!(label, "Luke-Jr");
+        assert_eq!(message, "Donation for project xyz");
+
+        assert_eq!(uri.to_string(), input);
+    }
+
+    #[test]
+    fn borrowed_str() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&message=Donation%20for%20project%20xyz";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        assert_eq!(uri.label.unwrap().try_as_borrowed_str(), Some("Luke-Jr"));
+        assert_eq!(uri.message.unwrap().try_as_borrowed_str(), None);
+        assert_eq!(crate::Param::from("foo").try_as_borrowed_str(), Some("foo"));
+        assert_eq!(crate::Param::from(alloc::string::String::from("foo")).try_as_borrowed_str(), None);
+    }
+
+    #[test]
+    fn recorded_keys() {
+        use crate::de::{DeserializationState, Recorded, RecordingState};
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=x&custom=y&req-other=z";
+        let uri = input.parse::<Uri<'_, _, Recorded<crate::NoExtras>>>();
+        assert!(uri.is_err());
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=x&custom=y&amount=1&other=z";
+        let uri = input.parse::<Uri<'_, _, Recorded<crate::NoExtras>>>().unwrap();
+        assert_eq!(uri.extras.visited_keys, ["custom", "other"]);
+
+        let mut state = RecordingState::<crate::EmptyState>::default();
+        assert!(state.visited_keys().is_empty());
+        let _ = state.deserialize_temp("foo", "bar".into()).unwrap();
+        assert_eq!(state.visited_keys(), ["foo"]);
+    }
+
+    #[test]
+    fn write_encoded() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=a%20b%41%3d%C5%BE&message=a%20b%41%3d%C5%BE";
+        let uri: Uri<'_, _> = input.try_into().unwrap();
+        let mut encoded = alloc::string::String::new();
+        uri.message.unwrap().write_encoded_to(&mut encoded).unwrap();
+        assert_eq!(encoded, "a%20bA%3D%C5%BE");
+        assert_eq!(uri.label.unwrap().into_encoded_string(), "a%20b%41%3d%C5%BE");
+        assert_eq!(crate::Param::from("a b").into_encoded_string(), "a%20b");
+    }
+
+    #[test]
+    fn as_encoded_str_or_encode() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=a%20b%41%3d%C5%BE";
+        let uri: Uri<'_, _> = input.try_into().unwrap();
+        let label = uri.label.unwrap();
+        let encoded = label.as_encoded_str_or_encode();
+        assert!(matches!(encoded, Cow::Borrowed(_)));
+        assert_eq!(encoded, "a%20b%41%3d%C5%BE");
+
+        let string = crate::Param::from("a b");
+        let encoded = string.as_encoded_str_or_encode();
+        assert!(matches!(encoded, Cow::Owned(_)));
+        assert_eq!(encoded, "a%20b");
+
+        #[cfg(feature = "non-compliant-bytes")]
+        {
+            let bytes = crate::Param::from(&b"a\xff"[..]);
+            let encoded = bytes.as_encoded_str_or_encode();
+            assert!(matches!(encoded, Cow::Owned(_)));
+            assert_eq!(encoded, "a%FF");
+        }
+    }
+
+    #[test]
+    fn required_not_understood() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-somethingyoudontunderstand=50&req-somethingelseyoudontget=999";
+        let uri = input.parse::<Uri<'_, _>>();
+        assert!(uri.is_err());
+    }
+
+    #[test]
+    fn required_understood() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?somethingyoudontunderstand=50&somethingelseyoudontget=999";
+        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
+        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert!(uri.amount.is_none());
+        assert!(uri.label.is_none());
+        assert!(uri.message.is_none());
+    }
+
+    #[test]
+    fn case_insensitive_keys() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?AMOUNT=1.0&Label=Luke-Jr";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        assert!(uri.amount.is_none());
+        assert!(uri.label.is_none());
+
+        let config = crate::ParseConfig {
+            case_insensitive_keys: true,
+            ..Default::default()
+        };
+        let uri: Uri<'_, _, crate::de::Recorded<crate::NoExtras>> = Uri::parse_with_config(input, &config).unwrap();
+        assert_eq!(uri.amount, Some(bitcoin::Amount::ONE_BTC));
+        assert_eq!(uri.label.unwrap().try_as_borrowed_str(), Some("Luke-Jr"));
+        assert!(uri.extras.visited_keys.is_empty());
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?Custom=x";
+        let uri: Uri<'_, _, crate::de::Recorded<crate::NoExtras>> = Uri::parse_with_config(input, &config).unwrap();
+        assert_eq!(uri.extras.visited_keys, ["Custom"]);
+    }
+
+    #[test]
+    fn network() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd";
+        assert!(Uri::<'_, _>::parse_with_network(input, bitcoin::Network::Bitcoin).is_ok());
+        assert!(Uri::<'_, _>::parse_with_network(input, bitcoin::Network::Testnet).is_err());
+
+        let config = crate::ParseConfig {
+            network: Some(bitcoin::Network::Bitcoin),
+            ..Default::default()
+        };
+        assert!(Uri::<'_, _>::parse_with_config(input, &config).is_ok());
+        let config = crate::ParseConfig {
+            network: Some(bitcoin::Network::Testnet),
+            ..Default::default()
+        };
+        assert!(Uri::<'_, _>::parse_with_config(input, &config).is_err());
+    }
+
+    #[test]
+    fn serialize_smart_pointers() {
+        use crate::SerializeParams;
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+        use alloc::sync::Arc;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr";
+        let uri = Uri::<'_, _>::parse(input).unwrap().assume_checked();
+        let mut boxed = Uri::with_extras(uri.address.clone(), Box::new(crate::NoExtras));
+        boxed.label = uri.label.clone();
+        assert_eq!(boxed.to_string(), input);
+        let mut rc = Uri::with_extras(uri.address.clone(), Rc::new(crate::NoExtras));
+        rc.label = uri.label.clone();
+        assert_eq!(rc.to_string(), input);
+        let mut arc = Uri::with_extras(uri.address.clone(), Arc::new(crate::NoExtras));
+        arc.label = uri.label.clone();
+        assert_eq!(arc.to_string(), input);
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1";
+        let uri = input.parse::<Uri<'_, _, Box<SingleParam<Lightning>>>>().unwrap();
+        assert_eq!(uri.extras.0.as_deref(), Some("lnbc1"));
+        assert_eq!((&mut crate::NoExtras).count_params(), 0);
+        assert_eq!(crate::NoExtras.count_params(), 0);
+    }
+
+    #[test]
+    fn param_pre_encoded() {
+        let param = crate::Param::from(crate::PreEncoded::new("Luke%20Jr").unwrap());
+        assert_eq!(param, "Luke Jr");
+        assert_eq!(param.into_encoded_string(), "Luke%20Jr");
+        assert_eq!(crate::Param::from("Luke%20Jr"), "Luke%20Jr");
+        assert!(crate::PreEncoded::new("Luke%2").is_err());
+        assert_eq!("Luke%20Jr".parse::<crate::Param<'static>>().unwrap(), "Luke Jr");
+        assert!("Luke%2".parse::<crate::Param<'static>>().is_err());
+    }
+
+    #[test]
+    fn param_re_encode() {
+        const SET: crate::AsciiSet = crate::CONTROLS.add(b'-').add(b' ');
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%2dJr%2C").unwrap();
+        let label = uri.label.unwrap();
+        assert_eq!(label.re_encode(&SET), "Luke%2DJr,");
+        assert_eq!(label.into_static().into_encoded_string(), "Luke-Jr,");
+    }
+
+    #[test]
+    fn non_empty_param() {
+        use crate::NonEmptyParam;
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=&message=%20").unwrap();
+        assert!(NonEmptyParam::try_from(uri.label.unwrap()).is_err());
+        let message = NonEmptyParam::try_from(uri.message.unwrap()).unwrap();
+        assert_eq!(message, " ");
+        assert!(!message.is_empty());
+    }
+
+    #[test]
+    fn param_decode_utf8() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%20Jr&message=%FF").unwrap();
+        let label = uri.label.unwrap();
+        assert_eq!(label.try_decode_utf8().unwrap(), "Luke Jr");
+        assert_eq!(label.decode_utf8_lossy(), "Luke Jr");
+        let message = uri.message.unwrap();
+        assert!(message.try_decode_utf8().is_err());
+        assert_eq!(message.decode_utf8_lossy(), "\u{FFFD}");
+    }
+
+    #[test]
+    fn param_write_decoded() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%20Jr&message=%FF").unwrap();
+        let mut string = alloc::string::String::from("Label: ");
+        uri.label.unwrap().write_decoded_to(&mut string).unwrap();
+        assert_eq!(string, "Label: Luke Jr");
+        assert!(uri.message.unwrap().write_decoded_to(&mut string).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "non-compliant-bytes")]
+    fn param_bytes_double_ended() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=a%2541%20b").unwrap();
+        let label = uri.label.unwrap();
+        assert!(label.bytes().rev().eq(b"b 14%a".iter().copied()));
+        let mut bytes = label.bytes();
+        assert_eq!(bytes.len(), 6);
+        assert_eq!(bytes.next(), Some(b'a'));
+        assert_eq!(bytes.next_back(), Some(b'b'));
+        assert_eq!(bytes.next_back(), Some(b' '));
+        assert_eq!(bytes.next(), Some(b'%'));
+        assert_eq!(bytes.len(), 2);
+        assert!(label.clone().into_bytes().rev().eq(b"b 14%a".iter().copied()));
+        assert!(crate::Param::from("abc").into_bytes().rev().eq(b"cba".iter().copied()));
+    }
+
+    #[test]
+    fn param_split_once() {
+        use crate::Param;
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=relay:key&message=relay%3Ak%C3%A9y").unwrap();
+        let label = uri.label.unwrap();
+        let (first, second) = label.split_once(':').unwrap();
+        assert_eq!(first, "relay");
+        assert_eq!(second, "key");
+        let message = uri.message.unwrap();
+        let (first, second) = message.split_once(':').unwrap();
+        assert_eq!(first, "relay");
+        assert_eq!(second, "kéy");
+        let (first, second) = message.split_once('é').unwrap();
+        assert_eq!(first, "relay:k");
+        assert_eq!(second, "y");
+
+        assert!(Param::from("relay").split_once(':').is_none());
+        let param = Param::from(":key:");
+        let (first, second) = param.split_once(':').unwrap();
+        assert!(first.is_empty());
+        assert_eq!(second, "key:");
+        let param = Param::from("relay:");
+        let (first, second) = param.split_once(':').unwrap();
+        assert_eq!(first, "relay");
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    fn param_truncate() {
+        use crate::Param;
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Caf%C3%A9%20au%20lait").unwrap();
+        let label = uri.label.unwrap();
+        assert_eq!(label.clone().truncate_decoded(5), "Café");
+        assert_eq!(label.clone().truncate_decoded(4), "Caf");
+        assert_eq!(label.clone().truncate_decoded(100), "Café au lait");
+        assert_eq!(label.clone().truncate_decoded_chars(4), "Café");
+        assert_eq!(label.clone().truncate_decoded_chars(5), "Café ");
+        assert_eq!(label.truncate_decoded_chars(0), "");
+        assert_eq!(Param::from("€").truncate_decoded(2), "");
+    }
+
+    #[test]
+    fn param_from_fmt() {
+        let param = crate::Param::from_fmt_args(format_args!("Invoice #{}", 42));
+        assert_eq!(alloc::string::String::try_from(param).unwrap(), "Invoice #42");
+        let param = crate::Param::from_fmt_args(format_args!("Donation"));
+        assert_eq!(param.try_as_borrowed_str(), Some("Donation"));
+    }
+
+    #[test]
+    fn param_into_static() {
+        let input = alloc::string::String::from("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%20Jr");
+        let label: crate::Param<'static> = Uri::<'_, _>::parse(&input).unwrap().label.map(crate::Param::into_static).unwrap();
+        drop(input);
+        assert_eq!(alloc::string::String::try_from(label).unwrap(), "Luke Jr");
+    }
+
+    #[test]
+    fn param_to_borrowed_str() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&message=Luke%20Jr";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        assert_eq!(<&str>::try_from(uri.label.unwrap()).unwrap(), "Luke-Jr");
+        assert!(<&str>::try_from(uri.message.unwrap()).is_err());
+        assert_eq!(<&str>::try_from(crate::Param::from("foo")).unwrap(), "foo");
+        assert!(<&str>::try_from(crate::Param::from(alloc::string::String::from("foo"))).is_err());
+    }
+
+    use crate::de::ParamKey;
+
+    /// Extras containing a single optional parameter.
+    #[derive(Debug, Default, Clone)]
+    struct SingleParam<K>(Option<alloc::string::String>, core::marker::PhantomData<K>);
+
+    impl<K> crate::DeserializationError for SingleParam<K> {
+        type Error = core::str::Utf8Error;
+    }
+
+    impl<K: ParamKey + Default> crate::DeserializeParams<'_> for SingleParam<K> {
+        type DeserializationState = Self;
+    }
+
+    impl<K: ParamKey + Default> crate::DeserializationState<'_> for SingleParam<K> {
+        type Value = Self;
+
+        fn is_param_known(&self, key: &str) -> bool {
+            key == K::KEY
+        }
+
+        fn deserialize_temp(&mut self, key: &str, value: crate::Param<'_>) -> Result<crate::de::ParamKind, core::str::Utf8Error> {
+            if key != K::KEY {
+                return Ok(crate::de::ParamKind::Unknown);
+            }
+            self.0 = Some(value.try_into()?);
+            Ok(crate::de::ParamKind::Known)
+        }
+
+        fn finalize(self) -> Result<Self, core::str::Utf8Error> {
+            Ok(self)
+        }
+    }
+
+    impl<'a, K> crate::SerializeParams for &'a SingleParam<K>
+    where
+        K: ParamKey,
+    {
+        type Key = &'static str;
+        type Value = &'a str;
+        type Iterator = core::option::IntoIter<(&'static str, &'a str)>;
+
+        fn serialize_params(self) -> Self::Iterator {
+            self.0.as_deref().map(|value| (K::KEY, value)).into_iter()
+        }
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct Lightning;
+
+    impl ParamKey for Lightning {
+        const KEY: &'static str = "lightning";
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct Payjoin;
+
+    impl ParamKey for Payjoin {
+        const KEY: &'static str = "req-pj";
+    }
+
+    /// Extras that serialize `req-pj` but don't understand it when parsing.
+    #[derive(Debug, Default)]
+    struct UnparsablePayjoin(SingleParam<Payjoin>);
+
+    impl crate::DeserializationError for UnparsablePayjoin {
+        type Error = core::convert::Infallible;
+    }
+
+    impl crate::DeserializeParams<'_> for UnparsablePayjoin {
+        type DeserializationState = Self;
+    }
+
+    impl crate::DeserializationState<'_> for UnparsablePayjoin {
+        type Value = Self;
+
+        fn is_param_known(&self, _key: &str) -> bool {
+            false
+        }
+
+        fn deserialize_temp(&mut self, _key: &str, _value: crate::Param<'_>) -> Result<crate::de::ParamKind, core::convert::Infallible> {
+            Ok(crate::de::ParamKind::Unknown)
+        }
+
+        fn finalize(self) -> Result<Self, core::convert::Infallible> {
+            Ok(self)
+        }
+    }
+
+    impl<'a> crate::SerializeParams for &'a UnparsablePayjoin {
+        type Key = &'static str;
+        type Value = &'a str;
+        type Iterator = core::option::IntoIter<(&'static str, &'a str)>;
+
+        fn serialize_params(self) -> Self::Iterator {
+            self.0.serialize_params()
+        }
+    }
+
+    #[test]
+    fn check_no_required_unknown() {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let payjoin = SingleParam::<Payjoin>(Some("https://example.com".into()), Default::default());
+
+        let uri = Uri::with_extras(address.clone(), payjoin.clone());
+        assert!(uri.check_no_required_unknown().is_ok());
+
+        let uri = Uri::with_extras(address.clone(), UnparsablePayjoin(payjoin));
+        let error = uri.check_no_required_unknown().unwrap_err();
+        assert_eq!(alloc::format!("{:?}", error), r#"UriError::UnknownRequiredParameter("req-pj")"#);
+
+        let uri = Uri::with_extras(address, UnparsablePayjoin::default());
+        assert!(uri.check_no_required_unknown().is_ok());
+    }
+
+    compose_extras! {
+        #[derive(Debug, Default, Clone)]
+        struct ComposedExtras {
+            lightning: SingleParam<Lightning>,
+            payjoin: SingleParam<Payjoin>,
+        }
+
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        enum ComposedExtrasError {
+            Lightning,
+            Payjoin,
+        }
+    }
+
+    #[test]
+    fn composed_extras() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&lightning=lnbc1&req-pj=https://example.com/pj";
+        let uri = Uri::<'_, _, ComposedExtras>::parse(input).unwrap().assume_checked();
+        assert_eq!(uri.extras.lightning.0.as_deref(), Some("lnbc1"));
+        assert_eq!(uri.extras.payjoin.0.as_deref(), Some("https://example.com/pj"));
+        assert_eq!(uri.to_string(), input);
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Luke-Jr&message=Hi%20there&lightning=lnbc1";
+        let uri = Uri::<'_, _, ComposedExtras>::parse(input).unwrap();
+        let params = uri.params().collect::<alloc::vec::Vec<_>>();
+        assert_eq!(params.len(), 4);
+        assert_eq!(params[0].0, "amount");
+        assert_eq!(params[0].1, "1");
+        assert_eq!(params[1].0, "label");
+        assert_eq!(params[1].1, "Luke-Jr");
+        assert_eq!(params[2].0, "message");
+        assert_eq!(params[2].1, "Hi there");
+        assert_eq!(params[3].0, "lightning");
+        assert_eq!(params[3].1, "lnbc1");
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-other=x";
+        assert!(Uri::<'_, _, ComposedExtras>::parse(input).is_err());
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=%FF";
+        assert!(matches!(
+            Uri::<'_, _, ComposedExtras>::parse(input),
+            Err(crate::de::Error::Extras(ComposedExtrasError::Lightning(_)))
+        ));
+    }
+
+    #[test]
+    fn serialize_vec() {
+        use crate::SerializeParams;
+
+        let mut lightning = ComposedExtras::default();
+        lightning.lightning.0 = Some("lnbc1".into());
+        let mut payjoin = ComposedExtras::default();
+        payjoin.payjoin.0 = Some("https://example.com".into());
+        let extras = alloc::vec![lightning, payjoin];
+        assert_eq!((&extras).count_params(), 2);
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        let uri = Uri::with_extras(uri.address.assume_checked(), extras);
+        assert_eq!(
+            uri.to_string(),
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1&req-pj=https://example.com"
+        );
+    }
+
+    #[test]
+    fn required_extras() {
+        use crate::de::{Error, RequiredExtras};
+
+        type Required = RequiredExtras<ComposedExtras, (Lightning, Payjoin)>;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1&req-pj=https://example.com";
+        let uri = input.parse::<Uri<'_, _, Required>>().unwrap();
+        assert_eq!(uri.extras.0.lightning.0.as_deref(), Some("lnbc1"));
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1";
+        match input.parse::<Uri<'_, _, Required>>() {
+            Err(Error::MissingRequiredExtras(error)) => assert_eq!(error.fields(), ["req-pj"]),
+            _ => panic!("expected missing parameter"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "either")]
+    fn either_extras() {
+        use either::Either;
+
+        type Extras = Either<SingleParam<Lightning>, SingleParam<Payjoin>>;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1";
+        let uri = input.parse::<Uri<'_, _, Extras>>().unwrap();
+        assert_eq!(uri.extras.as_ref().left().unwrap().0.as_deref(), Some("lnbc1"));
+        assert_eq!(uri.assume_checked().to_string(), input);
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-pj=https://example.com";
+        let uri = input.parse::<Uri<'_, _, Extras>>().unwrap();
+        assert_eq!(uri.extras.as_ref().right().unwrap().0.as_deref(), Some("https://example.com"));
+        assert_eq!(uri.assume_checked().to_string(), input);
+
+        let mut state = crate::de::EitherState::<SingleParam<Lightning>, SingleParam<Payjoin>>::default();
+        assert!(state.determine_variant().is_left());
+        let _ = crate::DeserializationState::deserialize_temp(&mut state, "req-pj", "x".into()).unwrap();
+        assert!(state.determine_variant().is_right());
+    }
+
+    /// Extras recognizing `req-a` only.
+    #[cfg(feature = "either")]
+    #[derive(Debug, Default, Clone)]
+    struct ReqA;
+
+    #[cfg(feature = "either")]
+    impl ParamKey for ReqA {
+        const KEY: &'static str = "req-a";
+    }
+
+    /// Extras recognizing `b` only.
+    #[cfg(feature = "either")]
+    #[derive(Debug, Default, Clone)]
+    struct B;
+
+    #[cfg(feature = "either")]
+    impl ParamKey for B {
+        const KEY: &'static str = "b";
+    }
+
+    #[test]
+    #[cfg(feature = "either")]
+    fn either_rejects_required_param_of_unselected_variant() {
+        use crate::de::Error;
+        use either::Either;
+
+        type Extras = Either<SingleParam<ReqA>, SingleParam<B>>;
+
+        for input in [
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-a=1&b=2",
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?b=2&req-a=1",
+        ] {
+            match Uri::<'_, _, Extras>::parse(input) {
+                Err(Error::Uri(error)) => assert!(alloc::format!("{}", error).contains("unknown required parameter 'req-a'")),
+                _ => panic!("unknown required parameter not rejected"),
+            }
+        }
+
+        let uri: Uri<'_, _, Extras> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-a=1").unwrap();
+        assert_eq!(uri.extras.left().unwrap().0.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn param_key_validation() {
+        use crate::ser::{validate_param_key, ValidParamKey};
+
+        assert!(validate_param_key("req-pj").is_ok());
+        let error = validate_param_key("foo#bar").unwrap_err();
+        assert_eq!(error.character(), Some('#'));
+        assert_eq!(error.position(), Some(3));
+        assert!(validate_param_key("ž").is_ok());
+        assert!(validate_param_key("a\0").is_ok());
+        assert!("foo=bar".parse::<ValidParamKey>().is_err());
+        assert_eq!("foo".parse::<ValidParamKey>().unwrap().as_str(), "foo");
+    }
+
+    #[test]
+    fn param_eq() {
+        use crate::Param;
+
+        assert_eq!(Param::from("hello"), "hello");
+        assert_eq!(Param::decode("hel%6Co").unwrap(), "hello");
+        assert_eq!("hello", Param::decode("hel%6Co").unwrap());
+        assert_eq!(Param::decode("hel%6Co").unwrap(), alloc::string::String::from("hello"));
+        assert_eq!(Param::decode("hel%6Co").unwrap(), Param::from("hello"));
+        assert_ne!(Param::from("hello"), "hell");
+        assert_ne!(Param::from("hello"), Param::decode("hello%20").unwrap());
+    }
+
+    #[test]
+    fn address_with_params() {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let uri: Uri<'_> = (address.clone(), "amount=1&label=Luke-Jr").try_into().unwrap();
+        assert_eq!(uri.address, address);
+        assert_eq!(uri.amount, Some(bitcoin::Amount::ONE_BTC));
+        assert_eq!(uri.label.unwrap(), "Luke-Jr");
+
+        let uri: Uri<'_> = (address.clone(), "").try_into().unwrap();
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert!(Uri::<'_, _, crate::NoExtras>::try_from((address, "req-foo=bar")).is_err());
+    }
+
+    #[test]
+    fn address_from_uri() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1").unwrap();
+        let address = bitcoin::Address::from(uri.clone().require_network(bitcoin::Network::Bitcoin).unwrap());
+        assert_eq!(address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert!(uri.require_network(bitcoin::Network::Testnet).is_err());
+    }
+
+    #[test]
+    fn diff() {
+        let old: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Luke%20Jr").unwrap();
+        let new: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke Jr&message=Hi").unwrap();
+        let diff = old.diff(&new);
+        assert!(!diff.address_changed);
+        assert!(diff.amount_changed);
+        assert!(!diff.label_changed);
+        assert!(diff.message_changed);
+        assert!(diff.has_changes());
+        assert!(!old.diff(&old).has_changes());
+    }
+
+    #[test]
+    fn serialize_to_writer() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        let uri = uri.assume_checked();
+        let mut string = alloc::string::String::new();
+        uri.serialize_to(&mut string).unwrap();
+        assert_eq!(string, input);
+        assert_eq!(alloc::string::String::from(&uri), input);
+        assert_eq!(alloc::string::String::from(uri.clone()), input);
+        #[cfg(feature = "std")]
+        {
+            let mut bytes = alloc::vec::Vec::new();
+            uri.format_to_bytes(&mut bytes).unwrap();
+            assert_eq!(bytes, input.as_bytes());
+        }
+    }
+
+    #[test]
+    fn clone_full_uri() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Luke%20Jr&message=Donation&lightning=lnbc1&req-pj=https://example.com";
+        let uri = input.parse::<Uri<'_, _, ComposedExtras>>().unwrap().assume_checked();
+        let cloned = uri.clone();
+        assert_eq!(cloned.to_string(), uri.to_string());
+    }
+
+    #[test]
+    fn with_amount() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd").unwrap();
+        let uri = uri.assume_checked().with_amount_sat(150_000);
+        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(150_000)));
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=0.0015");
+        let uri = uri.with_amount(bitcoin::Amount::ONE_BTC);
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1");
+    }
+
+    #[test]
+    fn display_as() {
+        use crate::ser::UriDisplayMode;
+
+        let input = "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?label=Luke-Jr";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        let uri = uri.assume_checked();
+        assert_eq!(uri.display_as(UriDisplayMode::Standard).to_string(), input);
+        assert_eq!(
+            uri.display_as(UriDisplayMode::Qr).to_string(),
+            "bitcoin:BC1QAR0SRRR7XFKVY5L643LYDNW9RE59GTZZWF5MDQ?label=Luke-Jr"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "test-utils")]
+    fn default_uri() {
+        let uri: Uri<'static> = Default::default();
+        assert_eq!(uri.address.to_string(), crate::DEFAULT_ADDRESS);
+        assert_eq!(uri.to_string(), alloc::format!("bitcoin:{}", crate::DEFAULT_ADDRESS));
+    }
+
+    #[test]
+    fn merge() {
+        use crate::merge::{MergeError, MergeStrategy};
+
+        let base: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Luke%20Jr").unwrap();
+        let other: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=2&message=Hi").unwrap();
+        let merged = base.clone().merge(other.clone()).unwrap();
+        assert_eq!(merged.amount, Some(bitcoin::Amount::from_int_btc(2)));
+        assert_eq!(merged.label.unwrap(), "Luke Jr");
+        assert_eq!(merged.message.unwrap(), "Hi");
+
+        let merged = base.clone().merge_with(other.clone(), MergeStrategy::PreferSelf).unwrap();
+        assert_eq!(merged.amount, Some(bitcoin::Amount::from_int_btc(1)));
+
+        match base.clone().merge_with(other, MergeStrategy::RejectConflicts) {
+            Err(MergeError::Conflict(diff)) => {
+                assert!(diff.amount_changed);
+                assert!(!diff.label_changed);
+            },
+            _ => panic!("expected conflict"),
+        }
+
+        let different: Uri<'_, _> = Uri::parse("bitcoin:1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH").unwrap();
+        match base.merge(different) {
+            Err(MergeError::Conflict(diff)) => assert!(diff.address_changed),
+            _ => panic!("expected address conflict"),
+        }
+    }
+
+    #[test]
+    fn param_constant_time_eq() {
+        use crate::Param;
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=s%65cret").unwrap();
+        let label = uri.label.unwrap();
+        assert!(Param::decoded_bytes_equal(&label, &Param::from("secret")));
+        assert!(!Param::decoded_bytes_equal(&label, &Param::from("secreT")));
+        assert!(!Param::decoded_bytes_equal(&label, &Param::from("secrets")));
+        assert!(!Param::decoded_bytes_equal(&label, &Param::empty()));
+    }
+
+    #[test]
+    fn semantically_equal() {
+        let a: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Luke%20Jr&message=Hi").unwrap();
+        let b: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?message=Hi&label=Luke Jr&amount=1.0").unwrap();
+        let c: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?message=Hi&label=Luke Jr").unwrap();
+        assert!(a.semantically_equal(&b));
+        assert!(b.semantically_equal(&a));
+        assert!(!a.semantically_equal(&c));
+    }
+
+    #[test]
+    fn param_amount() {
+        use crate::Param;
+
+        let amount = bitcoin::Amount::from_sat(1_2345_6789);
+        let param = Param::from(amount);
+        assert_eq!(param, "1.23456789");
+        assert_eq!(bitcoin::Amount::try_from(param).unwrap(), amount);
+        assert_eq!(
+            bitcoin::Amount::try_from(Param::decode("0%2E5").unwrap()).unwrap(),
+            bitcoin::Amount::from_sat(50_000_000)
+        );
+        assert!(bitcoin::Amount::try_from(Param::from("foo")).is_err());
+    }
+
+    #[test]
+    fn uri_error_details() {
+        use crate::de::Error;
+
+        match Uri::<'_, _>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label") {
+            Err(Error::Uri(error)) => assert_eq!(error.missing_equals_token(), Some("label")),
+            _ => panic!("expected missing equals"),
+        }
+        match Uri::<'_, _>::parse("litecoin:LhK2kQwiaAvhjWY799cZvMyYwnQAcxkarr") {
+            Err(Error::Uri(error)) => {
+                assert_eq!(error.invalid_scheme_found(), Some("litecoin"));
+                assert_eq!(error.missing_equals_token(), None);
+            },
+            _ => panic!("expected invalid scheme"),
+        }
+        match Uri::<'_, _>::parse("bitcoižžžžž") {
+            Err(Error::Uri(error)) => assert_eq!(error.invalid_scheme_found(), Some("bitcoižžžžž")),
+            _ => panic!("expected invalid scheme"),
+        }
+        match Uri::<'_, _>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-foo=bar") {
+            Err(Error::Uri(error)) => assert_eq!(alloc::format!("{:?}", error), r#"UriError::UnknownRequiredParameter("req-foo")"#),
+            _ => panic!("expected unknown required parameter"),
+        }
+        let long = alloc::format!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?{}", "x".repeat(100));
+        match Uri::<'_, _>::parse(&long) {
+            Err(Error::Uri(error)) => assert_eq!(
+                alloc::format!("{:?}", error),
+                alloc::format!("UriError::MissingEquals({:?}...)", "x".repeat(64))
+            ),
+            _ => panic!("expected missing equals"),
+        }
+        match Uri::<'_, _>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?message=%4") {
+            Err(Error::Uri(error)) => {
+                assert_eq!(error.percent_decode_parameter(), Some("message"));
+                assert!(error.percent_decode_error().is_some());
+                assert_eq!(error.percent_decode_context(), Some("%4"));
+            },
+            _ => panic!("expected percent decoding error"),
+        }
+        let long = alloc::format!(
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label={}hel%ZZo{}",
+            "x".repeat(40),
+            "ž".repeat(20)
+        );
+        match Uri::<'_, _>::parse(&long) {
+            Err(Error::Uri(error)) => {
+                let context = error.percent_decode_context().unwrap();
+                assert_eq!(context, alloc::format!("{}hel%ZZo{}", "x".repeat(12), "ž".repeat(6)));
+                assert!(alloc::format!("{}", error).contains(&alloc::format!("parameter label near '{}'", context)));
+            },
+            _ => panic!("expected percent decoding error"),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Luke%20Jr";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        let validated = uri.clone().validate(bitcoin::Network::Bitcoin).unwrap();
+        assert_eq!(validated.label.as_ref().unwrap(), "Luke Jr");
+        assert!(uri.clone().validate(bitcoin::Network::Testnet).is_err());
+
+        let mut excessive = uri.clone();
+        excessive.amount = Some(bitcoin::Amount::MAX_MONEY + bitcoin::Amount::ONE_SAT);
+        assert!(excessive.validate(bitcoin::Network::Bitcoin).is_err());
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1";
+        let uri = input.parse::<Uri<'_, _, SingleParam<Lightning>>>().unwrap();
+        let validator = |uri: &Uri<'static, _, SingleParam<Lightning>>| match uri.extras.0.as_deref() {
+            Some("lnbc1") => Ok(()),
+            _ => Err(alloc::string::String::from_utf8(alloc::vec![0xff]).unwrap_err().utf8_error()),
+        };
+        assert!(uri.validate_with(bitcoin::Network::Bitcoin, &validator).is_ok());
+    }
+
+    #[test]
+    fn cow_borrowed_is_zero_copy() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&message=Donation";
+        let uri: Uri<'_, _> = Cow::Borrowed(input).try_into().unwrap();
+        let label = uri.label.unwrap().try_as_borrowed_str().unwrap();
+        let message = uri.message.unwrap().try_as_borrowed_str().unwrap();
+        assert!(input.as_bytes().as_ptr_range().contains(&label.as_ptr()));
+        assert!(input.as_bytes().as_ptr_range().contains(&message.as_ptr()));
+
+        let uri: Uri<'_, _> = Cow::<str>::Owned(alloc::string::String::from(input)).try_into().unwrap();
+        assert_eq!(uri.label.unwrap(), "Luke-Jr");
+    }
+
+    #[test]
+    fn uri_error_conversion() {
+        use crate::de::{Error, UriError};
+
+        fn fails() -> Result<(), UriError> {
+            match Uri::<'_, _>::parse("bitcoin:") {
+                Err(Error::Uri(error)) => Err(error),
+                _ => Ok(()),
+            }
+        }
+
+        fn wrapped() -> Result<(), Error<core::str::Utf8Error>> {
+            fails()?;
+            Ok(())
+        }
+
+        assert!(matches!(wrapped(), Err(Error::Uri(_))));
+    }
+
+    #[test]
+    fn parse_str_is_static() {
+        let input = alloc::string::String::from("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr");
+        let uri: Uri<'static, _> = Uri::parse_str(&input).unwrap();
+        drop(input);
+        let label: Cow<'_, str> = uri.label.unwrap().try_into().unwrap();
+        assert_eq!(label, "Luke-Jr");
+    }
+
+    #[test]
+    fn payment_request_queue() {
+        use crate::queue::PaymentRequestQueue;
+
+        let input = alloc::string::String::from("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=first");
+        let first = Uri::<'_, _>::parse(&input).unwrap().assume_checked();
+        let mut queue = core::iter::once(first).collect::<PaymentRequestQueue>();
+        drop(input);
+
+        let input = alloc::string::String::from("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=second");
+        queue.push(Uri::<'_, _>::parse(&input).unwrap().assume_checked());
+        drop(input);
+        assert_eq!(queue.len(), 2);
+        let labels = queue
+            .iter()
+            .map(|uri| uri.label.as_ref().unwrap().decode_utf8_lossy().into_owned())
+            .collect::<alloc::vec::Vec<_>>();
+        assert_eq!(labels, ["first", "second"]);
+
+        let first = queue.pop().unwrap();
+        assert_eq!(first.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=first");
+        assert_eq!(queue.pop().unwrap().label.unwrap().decode_utf8_lossy(), "second");
+        assert!(queue.pop().is_none());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn shared_deserialization_state() {
+        use crate::DeserializationState;
+
+        let mut state = core::cell::RefCell::new(SingleParam::<Lightning>::default());
+        assert!(state.is_param_known("lightning"));
+        assert!(!state.is_param_known("req-pj"));
+        assert_eq!(state.deserialize_temp("lightning", "lnbc1".into()).unwrap(), crate::de::ParamKind::Known);
+        assert_eq!(state.borrow().0.as_deref(), Some("lnbc1"));
+        assert_eq!(state.finalize().unwrap().0.as_deref(), Some("lnbc1"));
+
+        #[cfg(feature = "std")]
+        {
+            let mut state = std::sync::Mutex::new(SingleParam::<Lightning>::default());
+            assert!(state.is_param_known("lightning"));
+            assert_eq!(state.deserialize_temp("other", "x".into()).unwrap(), crate::de::ParamKind::Unknown);
+            assert_eq!(state.deserialize_temp("lightning", "lnbc1".into()).unwrap(), crate::de::ParamKind::Known);
+            assert_eq!(state.finalize().unwrap().0.as_deref(), Some("lnbc1"));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn param_serde() {
+        use serde_test::{assert_de_tokens, assert_ser_tokens, Token};
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%20Jr&message=%FF").unwrap();
+        assert_ser_tokens(&uri.label.unwrap(), &[Token::Str("Luke Jr")]);
+        assert_de_tokens(&crate::Param::from("Luke Jr"), &[Token::Str("Luke Jr")]);
+        assert_de_tokens(&crate::Param::from("Luke Jr"), &[Token::String("Luke Jr")]);
+
+        #[cfg(feature = "non-compliant-bytes")]
+        {
+            assert_ser_tokens(&uri.message.unwrap(), &[Token::Bytes(&[0xff])]);
+            assert_de_tokens(&crate::Param::from(&[0xff][..]), &[Token::Bytes(&[0xff])]);
+        }
+        #[cfg(not(feature = "non-compliant-bytes"))]
+        serde_test::assert_ser_tokens_error(&uri.message.unwrap(), &[], "invalid utf-8 sequence of 1 bytes from index 0");
+    }
+
+    #[test]
+    fn param_from_string_refs() {
+        let string = alloc::string::String::from("Alice");
+        let param: crate::Param = (&string).into();
+        assert!(matches!(param.0, crate::ParamInner::UnencodedString(Cow::Borrowed(_))));
+        assert_eq!(param, "Alice");
+
+        let cow = Cow::<str>::Owned(string.clone());
+        let param = crate::Param::from(&cow);
+        assert!(matches!(param.0, crate::ParamInner::UnencodedString(Cow::Borrowed(_))));
+        assert_eq!(param, "Alice");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn uri_hash() {
+        use core::hash::{Hash, Hasher};
+
+        fn hash(uri: &Uri<'_, bitcoin::address::NetworkUnchecked>) -> u64 {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            uri.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Luke-Jr&message=Donation%20for%20project%20xyz";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        let mut set = std::collections::HashSet::new();
+        set.insert(uri);
+        assert!(set.contains(&Uri::parse(input).unwrap()));
+        assert!(!set.contains(&Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1").unwrap()));
+
+        let reencoded = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?message=Donation%20for%20project%20%78yz&label=%4Cuke-Jr&amount=1";
+        let reencoded: Uri<'_, _> = Uri::parse(reencoded).unwrap();
+        assert!(set.contains(&reencoded));
+        assert_eq!(hash(&reencoded), hash(&Uri::parse(input).unwrap()));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn param_decode_into_writer() {
+        let long = "a%20".repeat(100);
+        let input = alloc::format!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label={}&message=%FF", long);
+        let uri: Uri<'_, _> = Uri::parse(&input).unwrap();
+        let mut buf = alloc::vec::Vec::new();
+        uri.label.unwrap().try_decode_into_writer(&mut buf).unwrap();
+        assert_eq!(buf, "a ".repeat(100).as_bytes());
+
+        buf.clear();
+        uri.message.unwrap().try_decode_into_writer(&mut buf).unwrap();
+        assert_eq!(buf, [0xff]);
+
+        buf.clear();
+        crate::Param::from("a%20").try_decode_into_writer(&mut buf).unwrap();
+        assert_eq!(buf, b"a%20");
+    }
+
+    #[test]
+    fn serialize_to() {
+        let input = "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=1&label=Luke%20Jr";
+        let uri: Uri<'_, _> = Uri::parse(input).unwrap();
+        let uri = uri.assume_checked();
+        let mut string = alloc::string::String::new();
+        uri.serialize_to(&mut string).unwrap();
+        assert_eq!(string, input);
+
+        string.clear();
+        uri.serialize_to_qr(&mut string).unwrap();
+        assert_eq!(string, "bitcoin:BC1QAR0SRRR7XFKVY5L643LYDNW9RE59GTZZWF5MDQ?amount=1&label=Luke%20Jr");
+        assert_eq!(string, alloc::format!("{:#}", uri));
+    }
+
+    /// Extras containing a nested URI, failing with `UriError` if it's invalid.
+    #[derive(Debug, Default)]
+    struct Fallback;
+
+    impl crate::DeserializationError for Fallback {
+        type Error = crate::de::UriError;
+
+        fn into_uri_error(error: Self::Error) -> Result<crate::de::UriError, Self::Error> {
+            Ok(error)
+        }
+    }
+
+    impl crate::DeserializeParams<'_> for Fallback {
+        type DeserializationState = Self;
+    }
+
+    impl crate::DeserializationState<'_> for Fallback {
+        type Value = Self;
+
+        fn is_param_known(&self, key: &str) -> bool {
+            key == "fallback"
+        }
+
+        fn deserialize_temp(&mut self, key: &str, value: crate::Param<'_>) -> Result<crate::de::ParamKind, crate::de::UriError> {
+            if key != "fallback" {
+                return Ok(crate::de::ParamKind::Unknown);
+            }
+            let value = value.decode_utf8_lossy();
+            match Uri::<'_, _>::parse(&value) {
+                Ok(_) => Ok(crate::de::ParamKind::Known),
+                Err(crate::de::Error::Uri(error)) => Err(error),
+                Err(crate::de::Error::Extras(never)) => match never {},
+                Err(crate::de::Error::MissingRequiredExtras(_)) => unreachable!("no extras are required"),
+            }
+        }
+
+        fn finalize(self) -> Result<Self, crate::de::UriError> {
+            Ok(self)
+        }
+    }
+
+    #[test]
+    fn nested_uri_error() {
+        use crate::de::Error;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?fallback=foo:1andreas3batLhQa2FawWjeyjCqyBzypd";
+        match Uri::<'_, _, alloc::boxed::Box<Fallback>>::parse(input) {
+            Err(Error::Uri(error)) => assert_eq!(error.invalid_scheme_found(), Some("foo")),
+            _ => panic!("expected nested URI error"),
+        }
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?fallback=bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd";
+        assert!(Uri::<'_, _, Fallback>::parse(input).is_ok());
+    }
+
+    #[test]
+    fn param_integers() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=%34%32&message=-42").unwrap();
+        assert_eq!(u64::try_from(uri.label.clone().unwrap()).unwrap(), 42);
+        assert_eq!(u32::try_from(uri.label.clone().unwrap()).unwrap(), 42);
+        assert_eq!(i64::try_from(uri.message.clone().unwrap()).unwrap(), -42);
+        assert!(u64::try_from(uri.message.clone().unwrap()).is_err());
+        assert_eq!(uri.label.unwrap().parse::<u8>().unwrap(), 42);
+
+        assert_eq!(crate::Param::from(42u64), "42");
+        assert_eq!(crate::Param::from(-42i64), "-42");
+        assert_eq!(crate::Param::from(u32::MAX), "4294967295");
+    }
+
+    #[test]
+    fn try_with_extras() {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let uri = Uri::try_with_extras(address.clone(), |address| {
+            let value = alloc::format!("lightning:{}", address);
+            Ok::<_, core::convert::Infallible>(SingleParam::<Lightning>(Some(value), Default::default()))
+        })
+        .unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lightning:1andreas3batLhQa2FawWjeyjCqyBzypd"
+        );
+
+        let result = Uri::<'static, _, SingleParam<Lightning>>::try_with_extras(address, |_| Err("no invoice"));
+        assert_eq!(result.unwrap_err(), "no invoice");
+    }
 
-impl DeserializeParams<'_> for NoExtras {
-    type DeserializationState = EmptyState;
-}
+    #[test]
+    #[cfg(all(feature = "serde", feature = "std"))]
+    fn uri_serde() {
+        type UncheckedUri = Uri<'static, bitcoin::address::NetworkUnchecked, SingleParam<Lightning>>;
 
-impl DeserializationError for NoExtras {
-    type Error = core::convert::Infallible;
-}
+        let uri: UncheckedUri = serde_json::from_str(r#""bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Alice""#).unwrap();
+        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(100_000_000)));
+        assert_eq!(uri.label.unwrap(), "Alice");
 
-impl<'de> DeserializationState<'de> for EmptyState {
-    type Value = NoExtras;
+        let json = r#"{"address": "1andreas3batLhQa2FawWjeyjCqyBzypd", "amount": 0.1, "label": "Alice", "lightning": "lnbc1"}"#;
+        let uri: UncheckedUri = serde_json::from_str(json).unwrap();
+        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(10_000_000)));
+        assert_eq!(uri.label.unwrap(), "Alice");
+        assert!(uri.message.is_none());
+        assert_eq!(uri.extras.0.as_deref(), Some("lnbc1"));
 
-    fn is_param_known(&self, _key: &str) -> bool {
-        false
+        let uri: UncheckedUri = serde_json::from_str(r#"{"address": "1andreas3batLhQa2FawWjeyjCqyBzypd", "amount": "0.00000001"}"#).unwrap();
+        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(1)));
+
+        assert!(serde_json::from_str::<UncheckedUri>(r#"{"amount": 1}"#).is_err());
+        assert!(serde_json::from_str::<UncheckedUri>(r#"{"address": "1andreas3batLhQa2FawWjeyjCqyBzypd", "req-foo": "x"}"#).is_err());
+        assert!(serde_json::from_str::<UncheckedUri>(r#""bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-foo=x""#).is_err());
     }
 
-    fn deserialize_temp(&mut self, _key: &str, _value: Param<'_>) -> Result<de::ParamKind, <Self::Value as DeserializationError>::Error> {
-        Ok(de::ParamKind::Unknown)
+    #[test]
+    fn uri_ord() {
+        let inputs = [
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=b",
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd",
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=a&message=x",
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=a",
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=0.5",
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=%61&amount=1",
+        ];
+        let set = inputs
+            .iter()
+            .map(|input| Uri::<'_, _>::parse(input).unwrap())
+            .collect::<alloc::collections::BTreeSet<_>>();
+        let sorted = set
+            .iter()
+            .map(|uri| uri.clone().assume_checked().to_string())
+            .collect::<alloc::vec::Vec<_>>();
+        assert_eq!(
+            sorted,
+            [
+                "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd",
+                "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=0.5",
+                "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=a",
+                "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=a&message=x",
+                "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=b",
+            ]
+        );
     }
 
-    fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
-        Ok(Default::default())
+    #[test]
+    fn bip21_macro() {
+        let uri = crate::bip21!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr");
+        assert_eq!(uri.label.unwrap(), "Luke-Jr");
+        let uri = crate::bip21!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1", SingleParam<Lightning>);
+        assert_eq!(uri.extras.0.as_deref(), Some("lnbc1"));
     }
-}
 
-impl<'a> SerializeParams for &'a NoExtras {
-    type Key = core::convert::Infallible;
-    type Value = core::convert::Infallible;
-    type Iterator = core::iter::Empty<(Self::Key, Self::Value)>;
+    #[test]
+    #[should_panic(expected = "invalid BIP21 URI literal \"bitcoin:foo\"")]
+    fn bip21_macro_invalid() {
+        crate::bip21!("bitcoin:foo");
+    }
 
-    fn serialize_params(self) -> Self::Iterator {
-        core::iter::empty()
+    #[test]
+    #[cfg(feature = "base64")]
+    fn param_base64() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=%FB%FF%00a").unwrap();
+        assert_eq!(uri.label.unwrap().to_base64(), "-_8AYQ");
+        assert_eq!(crate::Param::from("hello").to_base64(), "aGVsbG8");
+
+        #[cfg(feature = "non-compliant-bytes")]
+        {
+            let param = crate::Param::from_base64("-_8AYQ").unwrap();
+            assert_eq!(alloc::vec::Vec::from(param.clone()), [0xfb, 0xff, 0x00, b'a']);
+            assert_eq!(param.to_base64(), "-_8AYQ");
+            assert_eq!(crate::Param::from_base64("aGVsbG8=").unwrap(), "hello");
+            assert!(crate::Param::from_base64("a+b/").is_err());
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::Uri;
-    use alloc::string::ToString;
-    use alloc::borrow::Cow;
-    use core::convert::TryInto;
+    #[test]
+    fn has_any_extras() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr").unwrap();
+        assert!(!uri.has_any_extras());
 
-    fn check_send_sync<T: Send + Sync>() {}
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr";
+        let uri = input.parse::<Uri<'_, _, SingleParam<Lightning>>>().unwrap();
+        assert!(!uri.has_any_extras());
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1";
+        let uri = input.parse::<Uri<'_, _, SingleParam<Lightning>>>().unwrap();
+        assert!(uri.has_any_extras());
+    }
 
     #[test]
-    fn send_sync() {
-        check_send_sync::<crate::de::UriError>();
+    #[cfg(feature = "heapless")]
+    fn param_heapless() {
+        use core::convert::TryFrom;
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%20Jr").unwrap();
+        let label = heapless::String::<8>::try_from(uri.label.clone().unwrap()).unwrap();
+        assert_eq!(label, "Luke Jr");
+        assert!(heapless::String::<6>::try_from(uri.label.unwrap()).is_err());
+
+        let mut string = heapless::String::<8>::new();
+        string.push_str("Luke-Jr").unwrap();
+        assert_eq!(crate::Param::from(string), "Luke-Jr");
+
+        #[cfg(feature = "non-compliant-bytes")]
+        {
+            let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=%FF").unwrap();
+            assert!(heapless::String::<8>::try_from(uri.label.unwrap()).is_err());
+            let bytes = heapless::Vec::<u8, 4>::from_slice(&[0xff, 0x00]).unwrap();
+            assert_eq!(alloc::vec::Vec::from(crate::Param::from(bytes)), [0xff, 0x00]);
+        }
     }
 
-    // Note: the official test vectors contained an invalid address so it was replaced with the address of Andreas Antonopoulos.
+    #[test]
+    fn parse_lenient_scheme() {
+        for input in [
+            "BITCOIN:1andreas3batLhQa2FawWjeyjCqyBzypd",
+            "Bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd",
+            "bItCoIn:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr",
+        ]
+        .iter()
+        {
+            let uri: Uri<'_, _> = Uri::parse_lenient(input).unwrap();
+            assert_eq!(uri.address.assume_checked().to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+            let uri: Uri<'_, _> = input.parse().unwrap();
+            assert_eq!(uri.address.assume_checked().to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        }
+        assert!(Uri::<'_, _>::parse_lenient("litecoin:1andreas3batLhQa2FawWjeyjCqyBzypd").is_err());
+    }
 
     #[test]
-    fn just_address() {
-        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd";
-        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
-        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
-        assert!(uri.amount.is_none());
-        assert!(uri.label.is_none());
-        assert!(uri.message.is_none());
+    fn scheme() {
+        assert_eq!(Uri::<'_, bitcoin::address::NetworkUnchecked>::scheme(), "bitcoin");
+        assert_eq!(crate::SCHEME, "bitcoin");
+        assert!(Uri::<'_, _>::parse("bitcoin1andreas3batLhQa2FawWjeyjCqyBzypd").is_err());
+        assert!(Uri::<'_, _>::parse("bitcoin:").is_err());
+    }
 
-        assert_eq!(uri.to_string(), input);
+    #[test]
+    #[cfg(feature = "std")]
+    fn payment_info() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=0.001&message=Donation%20for%20project%20xyz&lightning=lnbc1";
+        let uri = input.parse::<Uri<'_, _, SingleParam<Lightning>>>().unwrap();
+        let info = uri.to_payment_info();
+        assert_eq!(info.address, "1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert_eq!(info.amount_sat, Some(100_000));
+        assert_eq!(info.label, None);
+        assert_eq!(info.message.as_deref(), Some("Donation for project xyz"));
     }
 
     #[test]
-    fn address_with_name() {
+    fn clone_into_static() {
+        let input = alloc::string::String::from("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%20Jr&lightning=lnbc1");
+        let uri = Uri::<'_, _, SingleParam<Lightning>>::parse(&input).unwrap();
+        let cloned = uri.clone();
+        assert!(!cloned.diff(&uri).has_changes());
+        let owned: Uri<'static, _, SingleParam<Lightning>> = cloned.into_static();
+        drop(uri);
+        drop(input);
+        assert_eq!(owned.label.unwrap(), "Luke Jr");
+        assert_eq!(owned.extras.0.as_deref(), Some("lnbc1"));
+    }
+
+    #[test]
+    fn address_error() {
+        use crate::de::Error;
+
+        match Uri::<'_, _>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzype") {
+            Err(Error::Uri(error)) => {
+                assert!(error.as_address_error().is_some());
+                assert!(error.into_address_error().is_some());
+            },
+            _ => panic!("expected invalid address"),
+        }
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd").unwrap();
+        match uri.require_network(bitcoin::Network::Testnet) {
+            Err(Error::Uri(error)) => {
+                assert!(matches!(
+                    error.as_address_error(),
+                    Some(bitcoin::address::ParseError::NetworkValidation(_))
+                ))
+            },
+            _ => panic!("expected wrong network"),
+        }
+        match Uri::<'_, _>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=x") {
+            Err(Error::Uri(error)) => assert!(error.into_address_error().is_none()),
+            _ => panic!("expected invalid amount"),
+        }
+    }
+
+    #[test]
+    fn serialize_pair_extras() {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let uri = Uri::with_extras(address.clone(), ("lightning", "lnbc1"));
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1");
+        assert!(uri.has_any_extras());
+
+        let uri = Uri::with_extras(address.clone(), Some(("lightning", "lnbc1")));
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1");
+        let uri = Uri::with_extras(address.clone(), None::<(&str, &str)>);
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd");
+        assert!(!uri.has_any_extras());
+
+        let uri = Uri::with_extras(address, alloc::vec![("lightning", "lnbc1"), ("pj", "https://example.com")]);
+        assert_eq!(
+            uri.to_string(),
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1&pj=https://example.com"
+        );
+    }
+
+    #[test]
+    fn try_from_parts() {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let uri = Uri::try_from_parts(
+            address.clone(),
+            Some(bitcoin::Amount::from_sat(1000)),
+            Some("Luke-Jr"),
+            None::<&str>,
+            crate::NoExtras,
+        )
+        .unwrap();
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=0.00001&label=Luke-Jr");
+
+        let amount = bitcoin::Amount::MAX_MONEY + bitcoin::Amount::from_sat(1);
+        let error = Uri::try_from_parts(address.clone(), Some(amount), None::<&str>, None::<&str>, crate::NoExtras).unwrap_err();
+        assert_eq!(alloc::format!("{:?}", error), "UriError::ExcessiveAmount(2100000000000001 SAT)");
+
+        #[cfg(feature = "non-compliant-bytes")]
+        {
+            let error = Uri::try_from_parts(address, None, None::<&str>, Some(&[0xff][..]), crate::NoExtras).unwrap_err();
+            assert!(alloc::format!("{}", error).starts_with("the message is not valid UTF-8"));
+        }
+    }
+
+    #[test]
+    fn serialize_params_to_vec() {
+        use crate::SerializeParams;
+        use alloc::string::String;
+
+        let extras = alloc::vec![("pj", "https://example.com/?v=1"), ("lightning", "lnbc1")];
+        let mut params = extras.clone().to_vec();
+        params.sort();
+        assert_eq!(
+            params,
+            [
+                (String::from("lightning"), String::from("lnbc1")),
+                (String::from("pj"), String::from("https://example.com/?v=1"))
+            ]
+        );
+        assert_eq!(
+            extras.to_encoded_vec()[0],
+            (String::from("pj"), String::from("https://example.com/%3Fv%3D1"))
+        );
+        assert!(crate::NoExtras.to_vec().is_empty());
+    }
+
+    #[test]
+    fn redact() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=50&label=Luke-Jr&message=Donation%20for%20project%20xyz&lightning=lnbc1";
+        let uri = Uri::<'_, _, SingleParam<Lightning>>::parse(input).unwrap().assume_checked();
+        let redacted = uri.redact();
+        assert!(redacted.label.is_none());
+        assert!(redacted.message.is_none());
+        assert_eq!(redacted.amount, uri.amount);
+        assert_eq!(
+            redacted.to_string(),
+            "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=50&lightning=lnbc1"
+        );
+    }
+
+    #[test]
+    fn params_equal() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&lightning=lnbc1";
+        let with_lightning: Uri<'_, _, SingleParam<Lightning>> = Uri::parse(input).unwrap();
+        let same: Uri<'_, _, SingleParam<Lightning>> = Uri::parse(input).unwrap();
+        assert!(with_lightning.params_equal(&same));
+
         let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr";
-        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
-        let label: Cow<'_, str> = uri.label.clone().unwrap().try_into().unwrap();
-        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
-        assert_eq!(label, "Luke-Jr");
-        assert!(uri.amount.is_none());
-        assert!(uri.message.is_none());
+        let without_lightning: Uri<'_, _> = Uri::parse(input).unwrap();
+        assert!(!with_lightning.params_equal(&without_lightning));
+        assert!(!without_lightning.params_equal(&with_lightning));
 
-        assert_eq!(uri.to_string(), input);
+        let empty_lightning: Uri<'_, _, SingleParam<Lightning>> = Uri::parse(input).unwrap();
+        assert!(empty_lightning.params_equal(&without_lightning));
+
+        let pairs = Uri::with_extras(with_lightning.address.clone(), alloc::vec![("lightning", "lnbc1")]);
+        let mut with_lightning = with_lightning;
+        with_lightning.label = None;
+        assert!(with_lightning.params_equal(&pairs));
     }
 
-    #[allow(clippy::inconsistent_digit_grouping)] // Use sats/bitcoin when grouping.
     #[test]
-    fn request_20_point_30_btc_to_luke_dash_jr() {
-        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=20.3&label=Luke-Jr";
-        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
-        let label: Cow<'_, str> = uri.label.clone().unwrap().try_into().unwrap();
-        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
-        assert_eq!(label, "Luke-Jr");
-        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(20_30_000_000)));
-        assert!(uri.message.is_none());
+    fn param_decoded_sha256() {
+        use bitcoin::hashes::{sha256, Hash};
 
-        assert_eq!(uri.to_string(), input);
+        let long = "a%20".repeat(100);
+        let input = alloc::format!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%2DJr&message={}", long);
+        let uri: Uri<'_, _> = Uri::parse(&input).unwrap();
+        let expected = sha256::Hash::hash(b"Luke-Jr").to_byte_array();
+        assert_eq!(uri.label.unwrap().decoded_sha256(), expected);
+        assert_eq!(crate::Param::from("Luke-Jr").decoded_sha256(), expected);
+        let expected = sha256::Hash::hash("a ".repeat(100).as_bytes()).to_byte_array();
+        assert_eq!(uri.message.unwrap().decoded_sha256(), expected);
     }
 
-    #[allow(clippy::inconsistent_digit_grouping)] // Use sats/bitcoin when grouping.
     #[test]
-    fn request_50_btc_with_message() {
-        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=50&label=Luke-Jr&message=Donation%20for%20project%20xyz";
+    fn max_uri_length() {
+        use crate::de::Error;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr";
+        assert!(Uri::<'_, _>::parse_with_limit(input, input.len()).is_ok());
+        match Uri::<'_, _>::parse_with_limit(input, 32) {
+            Err(Error::Uri(error)) => {
+                assert_eq!(alloc::format!("{:?}", error), "UriError::TooLong { actual: 55, max: 32 }");
+                assert_eq!(alloc::format!("{}", error), "the URI is 55 bytes long, the maximum is 32");
+            },
+            _ => panic!("expected too long URI"),
+        }
+
+        let config = crate::ParseConfig {
+            max_uri_length: Some(1024),
+            ..Default::default()
+        };
+        assert!(Uri::<'_, _>::parse_with_config(input, &config).is_ok());
+        let input = alloc::format!("{}&message={}", input, "x".repeat(1024));
+        assert!(Uri::<'_, _>::parse_with_config(&input, &config).is_err());
+    }
+
+    #[test]
+    fn display_params() {
+        use crate::SerializeParams;
+
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let uri = Uri::with_extras(address, alloc::vec![("lightning", "lnbc1"), ("pj", "https://example.com/?v=1")]);
+        let displayed = (&uri.extras).display_params().to_string();
+        assert_eq!(displayed, "lightning=lnbc1&pj=https://example.com/%3Fv%3D1");
+        assert!(uri.to_string().ends_with(&alloc::format!("?{}", displayed)));
+
+        assert_eq!(crate::NoExtras.display_params().to_string(), "");
+        assert_eq!(crate::NoExtras.to_string(), "");
+    }
+
+    #[test]
+    fn precompute() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=50&label=Luke-Jr";
+        let uri = Uri::<'_, _>::parse(input).unwrap().assume_checked();
+        let precomputed = uri.precompute();
+        assert_eq!(&*precomputed, input);
+        assert_eq!(precomputed.as_str(), uri.to_string());
+        assert!(precomputed.starts_with("bitcoin:"));
+        assert_eq!(precomputed.to_string(), input);
+        assert_eq!(alloc::string::String::from(precomputed), input);
+    }
+
+    #[test]
+    fn address_type() {
+        use bitcoin::AddressType;
+
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd").unwrap();
+        assert_eq!(uri.address_type(), Some(AddressType::P2pkh));
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq").unwrap();
+        assert_eq!(uri.address_type(), Some(AddressType::P2wpkh));
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr").unwrap();
+        assert_eq!(uri.assume_checked().address_type(), Some(AddressType::P2tr));
+    }
+
+    #[test]
+    fn param_into_string_lossy() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke%20Jr&message=Caf%E9").unwrap();
+        assert_eq!(uri.label.unwrap().into_string_lossy(), "Luke Jr");
+        assert_eq!(uri.message.unwrap().into_string_lossy(), "Caf\u{FFFD}");
+        assert_eq!(crate::Param::from(alloc::string::String::from("Luke-Jr")).into_string_lossy(), "Luke-Jr");
+
+        #[cfg(feature = "non-compliant-bytes")]
+        assert_eq!(crate::Param::from(alloc::vec![b'a', 0xff]).into_string_lossy(), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn into_unchecked() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&lightning=lnbc1";
+        let uri: Uri<'_, _, SingleParam<Lightning>> = Uri::parse(input).unwrap();
+        let checked = uri.clone().require_network(bitcoin::Network::Bitcoin).unwrap();
+        let unchecked: Uri<'_, bitcoin::address::NetworkUnchecked, _> = checked.into_unchecked();
+        assert!(!unchecked.diff(&uri).has_changes());
+        assert_eq!(unchecked.extras.0.as_deref(), Some("lnbc1"));
+
+        let checked = uri.clone().assume_checked();
+        let unchecked: Uri<'_, bitcoin::address::NetworkUnchecked, _> = checked.into();
+        assert!(!unchecked.diff(&uri).has_changes());
+    }
+
+    #[test]
+    fn forwarded_unknown_params() {
+        use crate::de::Forwarded;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&lightning=lnbc1&foo=a%20b&bar=c";
+        let uri: Uri<'_, _, Forwarded<SingleParam<Lightning>>> = Uri::parse(input).unwrap();
+        assert_eq!(uri.extras.extras.0.as_deref(), Some("lnbc1"));
+        let unknown = &uri.extras.unknown_params;
+        assert_eq!(unknown.len(), 2);
+        assert_eq!(unknown[0].0, "foo");
+        assert_eq!(unknown[0].1, "a b");
+        assert_eq!(unknown[1].0, "bar");
+        assert_eq!(unknown[1].1, "c");
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-foo=a";
+        assert!(Uri::<'_, _, Forwarded<crate::NoExtras>>::parse(input).is_err());
+    }
+
+    #[test]
+    fn estimate_qr_complexity() {
+        let uri = Uri::<'_, _>::parse("bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")
+            .unwrap()
+            .assume_checked();
+        // 7 bytes for "bitcoin" and 43 alphanumeric characters fit into 40 codewords - version 3.
+        assert_eq!(uri.estimate_qr_complexity(), 29 * 29);
+
+        let input = "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=50&label=Luke-Jr&message=Donation%20for%20project%20xyz";
+        let uri = Uri::<'_, _>::parse(input).unwrap().assume_checked();
+        let complexity = uri.estimate_qr_complexity();
+        assert!(complexity > 29 * 29);
+        assert!(complexity < 57 * 57);
+
+        let mut uri = uri;
+        uri.message = Some(crate::Param::from("x".repeat(3000)));
+        assert_eq!(uri.estimate_qr_complexity(), usize::MAX);
+    }
+
+    #[test]
+    fn parse_lenient_with_warnings() {
+        use crate::de::ParseWarning;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=x&label=a&label=b&message=%ZZ&foo=1&lightning=lnbc1&bar";
+        let partial = Uri::<'_, _, SingleParam<Lightning>>::parse_lenient_with_warnings(input).unwrap();
+        assert_eq!(partial.uri.amount, None);
+        assert_eq!(partial.uri.label.clone().unwrap(), "b");
+        assert!(partial.uri.message.is_none());
+        assert_eq!(partial.uri.extras.0.as_deref(), Some("lnbc1"));
+        assert_eq!(
+            partial.warnings,
+            [
+                ParseWarning::InvalidAmount { value: "x".into() },
+                ParseWarning::DuplicateParameter {
+                    key: "label".into(),
+                    discarded_value: "a".into()
+                },
+                ParseWarning::InvalidEncoding { key: "message".into() },
+                ParseWarning::UnknownParameter { key: "foo".into() },
+                ParseWarning::MissingEquals { parameter: "bar".into() },
+            ]
+        );
+        assert!(!partial.is_payable());
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-foo=1";
+        let partial = Uri::<'_, _>::parse_lenient_with_warnings(input).unwrap();
+        assert_eq!(partial.warnings, [ParseWarning::UnknownRequiredParameter { key: "req-foo".into() }]);
+        assert!(!partial.is_payable());
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&foo=1";
+        let partial = Uri::<'_, _>::parse_lenient_with_warnings(input).unwrap();
+        assert!(partial.is_payable());
+        assert!(Uri::<'_, _>::parse_lenient_with_warnings("bitcoin:foo?label=x").is_err());
+    }
+
+    #[test]
+    fn type_aliases() {
+        use crate::{CheckedUri, StaticUri, UncheckedUri};
+
+        let uri: UncheckedUri<'_> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr").unwrap();
+        let checked: CheckedUri<'_> = uri.require_network(bitcoin::Network::Bitcoin).unwrap();
+        let owned: StaticUri = checked.into_static();
+        assert_eq!(owned.label.unwrap(), "Luke-Jr");
+    }
+
+    #[test]
+    fn error_display_includes_source() {
+        use crate::de::Error;
+
+        let result = Uri::<'_, _>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=x");
+        let error = match result {
+            Err(error @ Error::Uri(_)) => error,
+            _ => panic!("unexpected result"),
+        };
+        #[cfg(feature = "std")]
+        {
+            assert_eq!(alloc::format!("{}", error), "invalid BIP21 URI");
+            assert!(alloc::format!("{:#}", error).starts_with("invalid BIP21 URI: the amount is invalid: "));
+        }
+        #[cfg(not(feature = "std"))]
+        assert!(alloc::format!("{}", error).starts_with("invalid BIP21 URI: the amount is invalid: "));
+    }
+
+    #[test]
+    fn to_bytes() {
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr";
         let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
-        let label: Cow<'_, str> = uri.label.clone().unwrap().try_into().unwrap();
-        let message: Cow<'_, str> = uri.message.clone().unwrap().try_into().unwrap();
-        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
-        assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(50_00_000_000)));
-        assert_eq!(label, "Luke-Jr");
-        assert_eq!(message, "Donation for project xyz");
+        assert_eq!(uri.to_bytes(), input.as_bytes());
+
+        let mut buf = b"uri: ".to_vec();
+        uri.write_to_bytes(&mut buf);
+        assert_eq!(buf, alloc::format!("uri: {}", input).as_bytes());
+    }
+
+    /// Extras with an empty key, which is invalid.
+    struct EmptyKey;
+
+    impl crate::SerializeParams for &EmptyKey {
+        type Key = &'static str;
+        type Value = &'static str;
+        type Iterator = core::iter::Once<(&'static str, &'static str)>;
+
+        fn serialize_params(self) -> Self::Iterator {
+            core::iter::once(("", "value"))
+        }
+    }
+
+    fn empty_key_uri() -> Uri<'static, bitcoin::address::NetworkChecked, EmptyKey> {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        Uri::with_extras(address, EmptyKey)
+    }
+
+    #[test]
+    fn empty_param_key() {
+        use crate::ser::{validate_param_key, ValidParamKey};
+
+        let error = validate_param_key("").unwrap_err();
+        assert_eq!(error.character(), None);
+        assert_eq!(error.position(), None);
+        assert!(ValidParamKey::try_from("").is_err());
+
+        let uri = empty_key_uri();
+        let mut out = alloc::string::String::new();
+        assert!(uri.serialize_to(&mut out).is_err());
+        assert!(uri.serialize_to_qr(&mut out).is_err());
+        #[cfg(feature = "std")]
+        {
+            let mut bytes = alloc::vec::Vec::new();
+            let error = uri.format_to_bytes(&mut bytes).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "parameter key is empty")]
+    fn empty_param_key_display_panics() {
+        let _ = empty_key_uri().to_string();
+    }
+
+    #[test]
+    fn param_write_decoded_across_chunks() {
+        // The decoder works in 64-byte chunks, shifting the multi-byte characters over the
+        // boundary covers all split positions.
+        for padding in 56..64 {
+            let prefix = "a".repeat(padding);
+            let input = alloc::format!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label={}%C5%BE%E2%82%AC%F0%9F%98%80z", prefix);
+            let uri: Uri<'_, _> = Uri::parse(&input).unwrap();
+            let mut string = alloc::string::String::new();
+            uri.label.unwrap().write_decoded_to(&mut string).unwrap();
+            assert_eq!(string, alloc::format!("{}\u{17E}\u{20AC}\u{1F600}z", prefix));
+
+            let input = alloc::format!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label={}%E2%82", prefix);
+            let uri: Uri<'_, _> = Uri::parse(&input).unwrap();
+            assert!(uri.label.unwrap().write_decoded_to(&mut alloc::string::String::new()).is_err());
+
+            let input = alloc::format!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label={}%E2%82z", prefix);
+            let uri: Uri<'_, _> = Uri::parse(&input).unwrap();
+            assert!(uri.label.unwrap().write_decoded_to(&mut alloc::string::String::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_lenient_with_warnings_keeps_valid_duplicate() {
+        use crate::de::ParseWarning;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&amount=foo&label=a&label=%ZZ&message=b&message=%ZZ";
+        let partial = Uri::<'_, _>::parse_lenient_with_warnings(input).unwrap();
+        assert_eq!(partial.uri.amount, Some(bitcoin::Amount::from_sat(100_000_000)));
+        assert_eq!(partial.uri.label.clone().unwrap(), "a");
+        assert_eq!(partial.uri.message.clone().unwrap(), "b");
+        assert_eq!(
+            partial.warnings,
+            [
+                ParseWarning::InvalidAmount { value: "foo".into() },
+                ParseWarning::InvalidEncoding { key: "label".into() },
+                ParseWarning::InvalidEncoding { key: "message".into() },
+            ]
+        );
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=foo&amount=1&amount=2";
+        let partial = Uri::<'_, _>::parse_lenient_with_warnings(input).unwrap();
+        assert_eq!(partial.uri.amount, Some(bitcoin::Amount::from_sat(200_000_000)));
+        assert_eq!(
+            partial.warnings,
+            [
+                ParseWarning::InvalidAmount { value: "foo".into() },
+                ParseWarning::DuplicateParameter {
+                    key: "amount".into(),
+                    discarded_value: "1".into()
+                },
+            ]
+        );
+    }
+
+    /// Extras with mandatory `lightning` parameter reported by `require_fields`.
+    #[derive(Debug, Default)]
+    struct MandatoryLightning(SingleParam<Lightning>);
+
+    impl crate::DeserializationError for MandatoryLightning {
+        type Error = core::str::Utf8Error;
+    }
+
+    impl crate::DeserializeParams<'_> for MandatoryLightning {
+        type DeserializationState = Self;
+    }
+
+    impl crate::DeserializationState<'_> for MandatoryLightning {
+        type Value = Self;
+
+        fn is_param_known(&self, key: &str) -> bool {
+            self.0.is_param_known(key)
+        }
+
+        fn deserialize_temp(&mut self, key: &str, value: crate::Param<'_>) -> Result<crate::de::ParamKind, core::str::Utf8Error> {
+            self.0.deserialize_temp(key, value)
+        }
+
+        fn finalize(self) -> Result<Self, core::str::Utf8Error> {
+            Ok(self)
+        }
+
+        fn require_fields(&self) -> Result<(), crate::de::MissingFieldsError> {
+            match self.0 .0 {
+                Some(_) => Ok(()),
+                None => Err(crate::de::MissingFieldsError::new(["lightning"])),
+            }
+        }
+    }
+
+    #[test]
+    fn require_fields_custom_state() {
+        use crate::de::Error;
 
+        let uri: Uri<'_, _, MandatoryLightning> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1").unwrap();
+        assert_eq!(uri.extras.0 .0.as_deref(), Some("lnbc1"));
+
+        match Uri::<'_, _, MandatoryLightning>::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=foo") {
+            Err(Error::MissingRequiredExtras(error)) => assert_eq!(error.fields(), ["lightning"]),
+            _ => panic!("missing field not reported"),
+        }
+    }
+
+    #[test]
+    fn address_accessors() {
+        let uri: Uri<'_, _> = Uri::parse("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr").unwrap();
+        let uri = uri.require_network(bitcoin::Network::Bitcoin).unwrap();
+        assert_eq!(*uri.address(), uri.address);
+        let expected = uri.address.clone();
+        assert_eq!(uri.into_address(), expected);
+    }
+
+    #[test]
+    fn forwarded_round_trip() {
+        use crate::de::Forwarded;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=Luke-Jr&lightning=lnbc1&foo=a%20b&bar=c";
+        let uri: Uri<'_, _, Forwarded<SingleParam<Lightning>>> = Uri::parse(input).unwrap();
+        assert_eq!(uri.assume_checked().to_string(), input);
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?foo=%26";
+        let uri: Uri<'_, _, Forwarded<crate::NoExtras>> = Uri::parse(input).unwrap();
+        let uri = uri.assume_checked();
+        assert_eq!(crate::SerializeParams::param_count_hint(&&uri.extras), (1, Some(1)));
         assert_eq!(uri.to_string(), input);
     }
 
     #[test]
-    fn required_not_understood() {
-        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?req-somethingyoudontunderstand=50&req-somethingelseyoudontget=999";
-        let uri = input.parse::<Uri<'_, _>>();
-        assert!(uri.is_err());
+    fn validate_manually_constructed() {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd".parse::<bitcoin::Address<_>>().unwrap();
+        let payjoin = SingleParam::<Payjoin>(Some("https://example.com".into()), Default::default());
+        let uri = Uri::with_extras(address.clone(), UnparsablePayjoin(payjoin));
+        assert!(uri.validate(bitcoin::Network::Bitcoin).is_err());
+
+        #[cfg(feature = "non-compliant-bytes")]
+        {
+            let mut uri = Uri::<'_, _>::new(address);
+            uri.message = Some(crate::Param::from(&b"\xff"[..]));
+            assert!(uri.validate(bitcoin::Network::Bitcoin).is_err());
+        }
     }
 
     #[test]
-    fn required_understood() {
-        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?somethingyoudontunderstand=50&somethingelseyoudontget=999";
-        let uri = input.parse::<Uri<'_, _>>().unwrap().require_network(bitcoin::Network::Bitcoin).unwrap();
-        assert_eq!(uri.address.to_string(), "1andreas3batLhQa2FawWjeyjCqyBzypd");
-        assert!(uri.amount.is_none());
-        assert!(uri.label.is_none());
-        assert!(uri.message.is_none());
+    fn param_key_encoding() {
+        let address = "1andreas3batLhQa2FawWjeyjCqyBzypd"
+            .parse::<bitcoin::Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let uri = Uri::with_extras(address.clone(), alloc::vec![("a ž\0", "1")]);
+        assert_eq!(uri.to_string(), "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?a%20%C5%BE%00=1");
+
+        let uri = Uri::with_extras(address, alloc::vec![("a#b", "1")]);
+        assert!(uri.serialize_to(&mut alloc::string::String::new()).is_err());
+    }
+
+    #[test]
+    fn parse_lenient_with_warnings_missing_required_extras() {
+        use crate::de::ParseWarning;
+
+        let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?label=foo";
+        let partial = Uri::<'_, _, MandatoryLightning>::parse_lenient_with_warnings(input).unwrap();
+        assert_eq!(partial.uri.label.clone().unwrap(), "foo");
+        assert_eq!(
+            partial.warnings,
+            [ParseWarning::MissingRequiredExtras {
+                fields: alloc::vec!["lightning".into()]
+            }]
+        );
+        assert!(!partial.is_payable());
+    }
+
+    #[test]
+    fn mut_ref_param_count_hint() {
+        use crate::SerializeParams;
+
+        let mut extras = alloc::vec![("a", "1"), ("b", "2")];
+        let extras: &mut alloc::vec::Vec<(&str, &str)> = &mut extras;
+        assert_eq!(
+            <&mut alloc::vec::Vec<(&str, &str)> as SerializeParams>::param_count_hint(&extras),
+            (2, Some(2))
+        );
+        assert_eq!(extras.serialize_params().count(), 2);
     }
 }
@@ -0,0 +1,191 @@
+//! Macros provided by this crate.
+
+/// Composes multiple extras types into a single struct with named fields.
+///
+/// The macro generates the struct, an error enum with one variant per field (in the same order)
+/// and implements [`DeserializeParams`](crate::DeserializeParams) and
+/// [`SerializeParams`](crate::SerializeParams) for it by delegating to each field.
+///
+/// When deserializing, each parameter is offered to the fields in order until one of them reports
+/// it as [`Known`](crate::de::ParamKind::Known). When serializing, the parameters of all fields
+/// are emitted in order. Since the types of keys and values differ between fields they are
+/// formatted into `String`s, so serialization allocates.
+///
+/// The attributes of the error enum are passed through so you can derive `Debug` and other traits
+/// as long as the errors of the fields implement them.
+///
+/// ## Example
+///
+/// ```
+/// # type LightningExtras = bip21::NoExtras;
+/// # type PayjoinExtras = bip21::NoExtras;
+/// bip21::compose_extras! {
+///     /// Extras supported by our wallet.
+///     #[derive(Debug, Default)]
+///     pub struct WalletExtras {
+///         pub lightning: LightningExtras,
+///         pub payjoin: PayjoinExtras,
+///     }
+///
+///     /// Error returned when deserializing [`WalletExtras`] fails.
+///     #[derive(Debug)]
+///     pub enum WalletExtrasError {
+///         Lightning,
+///         Payjoin,
+///     }
+/// }
+///
+/// let input = "bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?lightning=lnbc1";
+/// let uri = input.parse::<bip21::Uri<'_, _, WalletExtras>>().unwrap();
+/// ```
+#[macro_export]
+macro_rules! compose_extras {
+    (
+        $(#[$struct_meta:meta])*
+        $struct_vis:vis struct $name:ident {
+            $($field_vis:vis $field:ident: $ty:ty),+ $(,)?
+        }
+
+        $(#[$error_meta:meta])*
+        $error_vis:vis enum $error:ident {
+            $($variant:ident),+ $(,)?
+        }
+    ) => {
+        $(#[$struct_meta])*
+        $struct_vis struct $name {
+            $($field_vis $field: $ty),+
+        }
+
+        $(#[$error_meta])*
+        $error_vis enum $error {
+            $(
+                #[allow(missing_docs)]
+                $variant(<$ty as $crate::de::DeserializationError>::Error)
+            ),+
+        }
+
+        impl $crate::de::DeserializationError for $name {
+            type Error = $error;
+
+            fn into_uri_error(error: Self::Error) -> Result<$crate::de::UriError, Self::Error> {
+                match error {
+                    $(
+                        $error::$variant(error) => <$ty as $crate::de::DeserializationError>::into_uri_error(error).map_err($error::$variant),
+                    )+
+                }
+            }
+        }
+
+        const _: () = {
+            use $crate::de::{DeserializationError, DeserializationState, DeserializeParams, ParamKind};
+            use $crate::Param;
+
+            #[allow(missing_docs)]
+            $struct_vis struct State<'de> where $($ty: DeserializeParams<'de>),+ {
+                $($field: <$ty as DeserializeParams<'de>>::DeserializationState),+
+            }
+
+            impl<'de> Default for State<'de> where $($ty: DeserializeParams<'de>),+ {
+                fn default() -> Self {
+                    State {
+                        $($field: Default::default()),+
+                    }
+                }
+            }
+
+            impl<'de> DeserializeParams<'de> for $name where $($ty: DeserializeParams<'de>),+ {
+                type DeserializationState = State<'de>;
+            }
+
+            impl<'de> DeserializationState<'de> for State<'de> where $($ty: DeserializeParams<'de>),+ {
+                type Value = $name;
+
+                fn is_param_known(&self, key: &str) -> bool {
+                    $(self.$field.is_param_known(key))||+
+                }
+
+                fn deserialize_temp(&mut self, key: &str, value: Param<'_>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+                    $(
+                        if self.$field.deserialize_temp(key, value.clone()).map_err($error::$variant)? == ParamKind::Known {
+                            return Ok(ParamKind::Known);
+                        }
+                    )+
+                    Ok(ParamKind::Unknown)
+                }
+
+                fn deserialize_borrowed(&mut self, key: &'de str, value: Param<'de>) -> Result<ParamKind, <Self::Value as DeserializationError>::Error> {
+                    $(
+                        if self.$field.deserialize_borrowed(key, value.clone()).map_err($error::$variant)? == ParamKind::Known {
+                            return Ok(ParamKind::Known);
+                        }
+                    )+
+                    Ok(ParamKind::Unknown)
+                }
+
+                fn finalize(self) -> Result<Self::Value, <Self::Value as DeserializationError>::Error> {
+                    Ok($name {
+                        $($field: self.$field.finalize().map_err($error::$variant)?),+
+                    })
+                }
+
+                fn require_fields(&self) -> Result<(), $crate::de::MissingFieldsError> {
+                    let mut missing = $crate::__private::Vec::new();
+                    $(
+                        if let Err(error) = self.$field.require_fields() {
+                            missing.extend(error.into_fields());
+                        }
+                    )+
+                    $crate::de::MissingFieldsError::check(missing)
+                }
+            }
+        };
+
+        impl<'a> $crate::SerializeParams for &'a $name where $(&'a $ty: $crate::SerializeParams),+ {
+            type Key = $crate::__private::String;
+            type Value = $crate::__private::String;
+            type Iterator = $crate::__private::vec::IntoIter<(Self::Key, Self::Value)>;
+
+            fn serialize_params(self) -> Self::Iterator {
+                use $crate::__private::ToString;
+
+                let mut params = $crate::__private::Vec::new();
+                $(
+                    params.extend(
+                        $crate::SerializeParams::serialize_params(&self.$field)
+                            .map(|(key, value)| (key.to_string(), value.to_string()))
+                    );
+                )+
+                params.into_iter()
+            }
+        }
+    };
+}
+
+/// Parses a literal BIP21 URI, panicking if it's invalid.
+///
+/// This is intended for tests and examples where the URI is hardcoded and it expands to
+/// [`Uri::parse`](crate::Uri::parse) followed by `expect`. The returned URI has
+/// [`NoExtras`](crate::NoExtras) unless the extras type is given as the second argument and its
+/// network is unchecked.
+///
+/// Note that the URI is validated when the code runs, not when it's compiled.
+///
+/// ## Example
+///
+/// ```
+/// let uri = bip21::bip21!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd?amount=1&label=Test").assume_checked();
+/// assert_eq!(uri.amount, Some(bitcoin::Amount::from_sat(100_000_000)));
+///
+/// let uri = bip21::bip21!("bitcoin:1andreas3batLhQa2FawWjeyjCqyBzypd", bip21::de::Recorded<bip21::NoExtras>);
+/// assert!(uri.extras.visited_keys.is_empty());
+/// ```
+#[macro_export]
+macro_rules! bip21 {
+    ($uri:literal) => {
+        $crate::bip21!($uri, $crate::NoExtras)
+    };
+    ($uri:literal, $extras:ty) => {
+        $crate::Uri::<'static, $crate::__private::NetworkUnchecked, $extras>::parse($uri)
+            .expect(concat!("invalid BIP21 URI literal ", stringify!($uri)))
+    };
+}
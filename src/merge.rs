@@ -0,0 +1,145 @@
+//! Types and traits related to merging of URIs
+//!
+//! This is useful when a URI containing only an address needs to be extended with other payment
+//! methods, such as Lightning invoice or Payjoin endpoint.
+//!
+//! Check [`Uri::merge`] to get started.
+
+use core::fmt;
+use bitcoin::address::NetworkValidation;
+use super::{Uri, UriDiff, NoExtras};
+
+/// Determines how fields present in both merged URIs are resolved.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum MergeStrategy {
+    /// The field from the other URI (the argument) is used.
+    PreferOther,
+
+    /// The field from this URI (the receiver) is used.
+    PreferSelf,
+
+    /// Merging fails if the fields differ.
+    RejectConflicts,
+}
+
+/// Represents extras that can be merged.
+///
+/// All values passed in `Extras` type parameter of [`Uri`] must implement this trait to allow
+/// merging.
+pub trait Merge: Sized {
+    /// Error returned when the extras can not be merged.
+    type Error;
+
+    /// Merges `other` into `self` resolving conflicts according to `strategy`.
+    fn merge(self, other: Self, strategy: MergeStrategy) -> Result<Self, Self::Error>;
+}
+
+impl Merge for NoExtras {
+    type Error = core::convert::Infallible;
+
+    fn merge(self, _other: Self, _strategy: MergeStrategy) -> Result<Self, Self::Error> {
+        Ok(NoExtras)
+    }
+}
+
+/// Error returned when merging URIs fails.
+#[derive(Debug, Clone)]
+pub enum MergeError<T> {
+    /// Some fields conflict.
+    ///
+    /// The addresses always have to be the same, other fields conflict only if
+    /// [`MergeStrategy::RejectConflicts`] was used.
+    Conflict(UriDiff),
+
+    /// Merging of extras failed.
+    Extras(T),
+}
+
+impl<T: fmt::Display> fmt::Display for MergeError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeError::Conflict(diff) => {
+                write!(f, "the URIs have conflicting")?;
+                let fields = [
+                    (diff.address_changed, "address"),
+                    (diff.amount_changed, "amount"),
+                    (diff.label_changed, "label"),
+                    (diff.message_changed, "message"),
+                ];
+                let mut separator = " ";
+                for (_, field) in fields.iter().filter(|(changed, _)| *changed) {
+                    write!(f, "{}{}", separator, field)?;
+                    separator = ", ";
+                }
+                Ok(())
+            },
+            #[cfg(feature = "std")]
+            MergeError::Extras(_) => write!(f, "failed to merge extras"),
+            #[cfg(not(feature = "std"))]
+            MergeError::Extras(error) => write!(f, "failed to merge extras: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl<T: fmt::Display + std::error::Error + 'static> std::error::Error for MergeError<T> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MergeError::Conflict(_) => None,
+            MergeError::Extras(error) => Some(error),
+        }
+    }
+}
+
+/// Resolves a single field, setting `conflict` if the values can not be merged.
+fn merge_field<V: PartialEq>(this: Option<V>, other: Option<V>, strategy: MergeStrategy, conflict: &mut bool) -> Option<V> {
+    match (this, other) {
+        (Some(this), Some(other)) => match strategy {
+            MergeStrategy::PreferOther => Some(other),
+            MergeStrategy::PreferSelf => Some(this),
+            MergeStrategy::RejectConflicts => {
+                *conflict |= this != other;
+                Some(this)
+            },
+        },
+        (this, None) => this,
+        (None, other) => other,
+    }
+}
+
+impl<'a, NetVal: NetworkValidation, T: Merge> Uri<'a, NetVal, T> {
+    /// Merges two URIs with the same address, preferring fields from `other`.
+    ///
+    /// This is the same as calling [`merge_with`](Self::merge_with) with
+    /// [`MergeStrategy::PreferOther`].
+    pub fn merge(self, other: Self) -> Result<Self, MergeError<T::Error>> {
+        self.merge_with(other, MergeStrategy::PreferOther)
+    }
+
+    /// Merges two URIs with the same address resolving conflicts according to `strategy`.
+    ///
+    /// Fields present in only one of the URIs are always used. Parameters are compared by their
+    /// decoded values.
+    pub fn merge_with(self, other: Self, strategy: MergeStrategy) -> Result<Self, MergeError<T::Error>> {
+        let mut conflicts = UriDiff {
+            address_changed: self.address.as_unchecked() != other.address.as_unchecked(),
+            ..Default::default()
+        };
+        let amount = merge_field(self.amount, other.amount, strategy, &mut conflicts.amount_changed);
+        let label = merge_field(self.label, other.label, strategy, &mut conflicts.label_changed);
+        let message = merge_field(self.message, other.message, strategy, &mut conflicts.message_changed);
+        if conflicts.has_changes() {
+            return Err(MergeError::Conflict(conflicts));
+        }
+        let extras = self.extras.merge(other.extras, strategy).map_err(MergeError::Extras)?;
+
+        Ok(Uri {
+            address: self.address,
+            amount,
+            label,
+            message,
+            extras,
+        })
+    }
+}
@@ -0,0 +1,94 @@
+//! Queue of payment requests
+//!
+//! Wallet backends often collect multiple payment requests before processing them. This module
+//! provides [`PaymentRequestQueue`] which owns the URIs so they can outlive the strings they were
+//! parsed from.
+
+use alloc::collections::vec_deque::{self, VecDeque};
+use bitcoin::address::NetworkValidation;
+use core::iter::FromIterator;
+use super::{Uri, NoExtras};
+
+/// First-in, first-out queue of payment requests.
+///
+/// The URIs are converted to `'static` when inserted by decoding their labels and messages, the
+/// extras are moved as-is.
+#[derive(Debug, Clone)]
+pub struct PaymentRequestQueue<NetVal = bitcoin::address::NetworkChecked, Extras = NoExtras>
+where
+    NetVal: NetworkValidation,
+{
+    requests: VecDeque<Uri<'static, NetVal, Extras>>,
+}
+
+impl<NetVal: NetworkValidation, T> PaymentRequestQueue<NetVal, T> {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        PaymentRequestQueue { requests: VecDeque::new() }
+    }
+
+    /// Appends the payment request to the end of the queue.
+    pub fn push(&mut self, uri: Uri<'_, NetVal, T>) {
+        self.requests.push_back(uri.into_static());
+    }
+
+    /// Removes the oldest payment request from the queue.
+    ///
+    /// Returns `None` if the queue is empty.
+    pub fn pop(&mut self) -> Option<Uri<'static, NetVal, T>> {
+        self.requests.pop_front()
+    }
+
+    /// Returns an iterator over the payment requests from the oldest to the newest.
+    pub fn iter(&self) -> vec_deque::Iter<'_, Uri<'static, NetVal, T>> {
+        self.requests.iter()
+    }
+
+    /// Returns the number of payment requests in the queue.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if there are no payment requests in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+impl<NetVal: NetworkValidation, T> Default for PaymentRequestQueue<NetVal, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, NetVal: NetworkValidation, T> Extend<Uri<'a, NetVal, T>> for PaymentRequestQueue<NetVal, T> {
+    fn extend<I: IntoIterator<Item = Uri<'a, NetVal, T>>>(&mut self, iter: I) {
+        self.requests.extend(iter.into_iter().map(Uri::into_static));
+    }
+}
+
+impl<'a, NetVal: NetworkValidation, T> FromIterator<Uri<'a, NetVal, T>> for PaymentRequestQueue<NetVal, T> {
+    fn from_iter<I: IntoIterator<Item = Uri<'a, NetVal, T>>>(iter: I) -> Self {
+        let mut queue = Self::new();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<NetVal: NetworkValidation, T> IntoIterator for PaymentRequestQueue<NetVal, T> {
+    type Item = Uri<'static, NetVal, T>;
+    type IntoIter = vec_deque::IntoIter<Uri<'static, NetVal, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.requests.into_iter()
+    }
+}
+
+impl<'a, NetVal: NetworkValidation, T> IntoIterator for &'a PaymentRequestQueue<NetVal, T> {
+    type Item = &'a Uri<'static, NetVal, T>;
+    type IntoIter = vec_deque::Iter<'a, Uri<'static, NetVal, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
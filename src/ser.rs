@@ -4,10 +4,17 @@
 //!
 //! Check [`SerializeParams`] to get started.
 
-use alloc::borrow::Cow;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use bitcoin::amount::Denomination;
+use core::convert::{TryFrom, TryInto};
 use core::fmt;
 use super::{Uri, Param, ParamInner};
+use percent_encoding_rfc3986::AsciiSet;
 
 /// Represents a value that can be serialized.
 ///
@@ -15,7 +22,9 @@ use super::{Uri, Param, ParamInner};
 pub trait SerializeParams {
     /// Parameter name.
     ///
-    /// **Warning**: displaying [`Uri`] will panic if the key contains `=` character!
+    /// **Warning**: displaying [`Uri`] will panic if the key is empty or contains a disallowed
+    /// character! [`Uri::serialize_to`] returns an error instead.
+    /// See [`validate_param_key`] for details and [`ValidParamKey`] for a type that prevents this.
     type Key: fmt::Display;
     /// Parameter value.
     type Value: fmt::Display;
@@ -25,29 +34,612 @@ pub trait SerializeParams {
 
     /// Constructs the iterator over key-value pairs.
     fn serialize_params(self) -> Self::Iterator;
+
+    /// Returns the bounds on the number of parameters that will be serialized.
+    ///
+    /// This has the same semantics as [`Iterator::size_hint`] and is intended for capacity
+    /// estimation. The default implementation returns `(0, None)` which is correct for any type.
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+
+    /// Counts the parameters by exhausting the iterator.
+    fn count_params(self) -> usize
+    where
+        Self: Sized,
+    {
+        self.serialize_params().count()
+    }
+
+    /// Collects the parameters into a vector of owned key-value pairs.
+    ///
+    /// The values are **not** percent-encoded, they are the outputs of their `Display`
+    /// implementations. This is useful for manipulating the parameters as a collection, e.g.
+    /// sorting them to get deterministic output.
+    fn to_vec(self) -> Vec<(String, String)>
+    where
+        Self: Sized,
+    {
+        self.serialize_params().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    /// Collects the parameters into a vector of owned key-value pairs with encoded values.
+    ///
+    /// The values are percent-encoded the same way as when displaying [`Uri`].
+    fn to_encoded_vec(self) -> Vec<(String, String)>
+    where
+        Self: Sized,
+    {
+        self.serialize_params()
+            .map(|(key, value)| (key.to_string(), DisplayEncoder(value).to_string()))
+            .collect()
+    }
+
+    /// Returns a value that displays the parameters the same way as they appear in the URI.
+    ///
+    /// The parameters are separated by `&` and the values are percent-encoded, the leading `?`
+    /// is not included. This is mainly useful for debugging, e.g.
+    /// `log::debug!("extras: {}", (&uri.extras).display_params())`.
+    fn display_params(self) -> DisplayParams<Self>
+    where
+        Self: Sized + Clone,
+    {
+        DisplayParams(self)
+    }
 }
 
-/// Checks if the display implementation outputs `=` character.
-struct EqSignChecker<'a, W: fmt::Write>(W, &'a dyn fmt::Display);
+/// Displays parameters of extras.
+///
+/// This is returned from [`SerializeParams::display_params`].
+#[derive(Debug, Clone)]
+pub struct DisplayParams<T>(T);
 
-impl<'a, W: fmt::Write> fmt::Write for EqSignChecker<'a, W> {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        if s.contains('=') {
-            panic!("key '{}' contains equal sign", self.1);
+impl<T: SerializeParams + Clone> fmt::Display for DisplayParams<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut separator = "";
+        for (key, value) in self.0.clone().serialize_params() {
+            write!(f, "{}{}={}", separator, key, DisplayEncoder(value))?;
+            separator = "&";
         }
-        self.0.write_str(s)
+        Ok(())
+    }
+}
+
+/// Types whose references implement [`SerializeParams`].
+///
+/// This is implemented for all types `T` where `&T` implements [`SerializeParams`]. Unlike
+/// `for<'a> &'a T: SerializeParams` it can be used as a bound on a type that is not yet known
+/// when the method is called, which would otherwise make the compiler fail with an overflow.
+pub trait SerializeParamsRef {
+    /// Collects the parameters into a vector of owned key-value pairs.
+    ///
+    /// This is the same as calling [`SerializeParams::to_vec`] on a reference.
+    fn params_to_vec(&self) -> Vec<(String, String)>;
+}
+
+impl<T: ?Sized> SerializeParamsRef for T
+where
+    for<'a> &'a T: SerializeParams,
+{
+    fn params_to_vec(&self) -> Vec<(String, String)> {
+        SerializeParams::to_vec(self)
+    }
+}
+
+/// Serializes the value behind the mutable reference the same way as behind a shared one.
+impl<'a, T: ?Sized> SerializeParams for &'a mut T
+where
+    for<'b> &'b T: SerializeParams,
+{
+    type Key = <&'a T as SerializeParams>::Key;
+    type Value = <&'a T as SerializeParams>::Value;
+    type Iterator = <&'a T as SerializeParams>::Iterator;
+
+    fn serialize_params(self) -> Self::Iterator {
+        (&*self).serialize_params()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        (&**self).param_count_hint()
+    }
+}
+
+impl<T: SerializeParams> SerializeParams for Box<T> {
+    type Key = T::Key;
+    type Value = T::Value;
+    type Iterator = T::Iterator;
+
+    fn serialize_params(self) -> Self::Iterator {
+        (*self).serialize_params()
     }
 
-    fn write_char(&mut self, c: char) -> fmt::Result {
-        if c == '=' {
-            panic!("key '{}' contains equal sign", self.1);
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        (**self).param_count_hint()
+    }
+}
+
+impl<'a, T: ?Sized> SerializeParams for &'a Box<T>
+where
+    &'a T: SerializeParams,
+{
+    type Key = <&'a T as SerializeParams>::Key;
+    type Value = <&'a T as SerializeParams>::Value;
+    type Iterator = <&'a T as SerializeParams>::Iterator;
+
+    fn serialize_params(self) -> Self::Iterator {
+        (&**self).serialize_params()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        let inner: &'a T = self;
+        inner.param_count_hint()
+    }
+}
+
+impl<'a, T: ?Sized> SerializeParams for &'a Rc<T>
+where
+    &'a T: SerializeParams,
+{
+    type Key = <&'a T as SerializeParams>::Key;
+    type Value = <&'a T as SerializeParams>::Value;
+    type Iterator = <&'a T as SerializeParams>::Iterator;
+
+    fn serialize_params(self) -> Self::Iterator {
+        (&**self).serialize_params()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        let inner: &'a T = self;
+        inner.param_count_hint()
+    }
+}
+
+impl<'a, T: ?Sized> SerializeParams for &'a Arc<T>
+where
+    &'a T: SerializeParams,
+{
+    type Key = <&'a T as SerializeParams>::Key;
+    type Value = <&'a T as SerializeParams>::Value;
+    type Iterator = <&'a T as SerializeParams>::Iterator;
+
+    fn serialize_params(self) -> Self::Iterator {
+        (&**self).serialize_params()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        let inner: &'a T = self;
+        inner.param_count_hint()
+    }
+}
+
+/// Sums the bounds returned by `param_count_hint` of all elements.
+fn sum_count_hints(hints: impl Iterator<Item = (usize, Option<usize>)>) -> (usize, Option<usize>) {
+    hints.fold((0, Some(0)), |(lower, upper), (elem_lower, elem_upper)| {
+        let upper = match (upper, elem_upper) {
+            (Some(upper), Some(elem_upper)) => upper.checked_add(elem_upper),
+            _ => None,
+        };
+        (lower.saturating_add(elem_lower), upper)
+    })
+}
+
+/// Serializes parameters of all elements in order.
+impl<T: SerializeParams> SerializeParams for Vec<T> {
+    type Key = T::Key;
+    type Value = T::Value;
+    type Iterator = core::iter::FlatMap<alloc::vec::IntoIter<T>, T::Iterator, fn(T) -> T::Iterator>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        self.into_iter().flat_map(T::serialize_params as fn(T) -> T::Iterator)
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        sum_count_hints(self.iter().map(T::param_count_hint))
+    }
+}
+
+/// Serializes parameters of all elements in order.
+impl<'a, T> SerializeParams for &'a Vec<T>
+where
+    &'a T: SerializeParams,
+{
+    type Key = <&'a T as SerializeParams>::Key;
+    type Value = <&'a T as SerializeParams>::Value;
+    type Iterator =
+        core::iter::FlatMap<core::slice::Iter<'a, T>, <&'a T as SerializeParams>::Iterator, fn(&'a T) -> <&'a T as SerializeParams>::Iterator>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        self.iter().flat_map(<&'a T as SerializeParams>::serialize_params as fn(&'a T) -> _)
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        sum_count_hints(self.iter().map(|elem| elem.param_count_hint()))
+    }
+}
+
+/// Serializes exactly one parameter.
+///
+/// This is the simplest way to implement extras consisting of a single mandatory parameter -
+/// return `(key, value)` from `serialize_params` or use the pair directly. Combined with the
+/// implementation for `Vec` this also allows `Vec<(K, V)>` to be used as dynamic extras.
+impl<K: fmt::Display, V: fmt::Display> SerializeParams for (K, V) {
+    type Key = K;
+    type Value = V;
+    type Iterator = core::iter::Once<(K, V)>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        core::iter::once(self)
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        (1, Some(1))
+    }
+}
+
+/// Serializes exactly one parameter.
+impl<'a, K: fmt::Display, V: fmt::Display> SerializeParams for &'a (K, V) {
+    type Key = &'a K;
+    type Value = &'a V;
+    type Iterator = core::iter::Once<(&'a K, &'a V)>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        core::iter::once((&self.0, &self.1))
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        (1, Some(1))
+    }
+}
+
+/// Serializes the parameter if present.
+///
+/// This is the simplest way to implement extras consisting of a single optional parameter.
+impl<K: fmt::Display, V: fmt::Display> SerializeParams for Option<(K, V)> {
+    type Key = K;
+    type Value = V;
+    type Iterator = core::option::IntoIter<(K, V)>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        self.into_iter()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        let count = usize::from(self.is_some());
+        (count, Some(count))
+    }
+}
+
+/// Serializes the parameter if present.
+impl<'a, K: fmt::Display, V: fmt::Display> SerializeParams for &'a Option<(K, V)> {
+    type Key = &'a K;
+    type Value = &'a V;
+    type Iterator = core::option::IntoIter<(&'a K, &'a V)>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        self.as_ref().map(|(key, value)| (key, value)).into_iter()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        let count = usize::from(self.is_some());
+        (count, Some(count))
+    }
+}
+
+/// Serializes the inner extras followed by the unknown parameters.
+///
+/// The unknown parameters are displayed the same way they were parsed, except for needlessly
+/// encoded characters getting decoded. Values that are not valid UTF-8 (which BIP21 forbids) are
+/// decoded lossily.
+impl<'a, T> SerializeParams for &'a crate::de::Forwarded<T>
+where
+    &'a T: SerializeParams,
+{
+    type Key = ForwardedItem<'a, <&'a T as SerializeParams>::Key>;
+    type Value = ForwardedItem<'a, <&'a T as SerializeParams>::Value>;
+    type Iterator = ForwardedParams<'a, <&'a T as SerializeParams>::Iterator>;
+
+    fn serialize_params(self) -> Self::Iterator {
+        ForwardedParams {
+            inner: self.extras.serialize_params(),
+            unknown: self.unknown_params.iter(),
+        }
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        let extras: &'a T = &self.extras;
+        let unknown = self.unknown_params.len();
+        sum_count_hints([extras.param_count_hint(), (unknown, Some(unknown))].iter().copied())
+    }
+}
+
+/// Iterator over parameters of [`Forwarded`](crate::de::Forwarded) extras.
+pub struct ForwardedParams<'a, I> {
+    inner: I,
+    unknown: core::slice::Iter<'a, (String, Param<'static>)>,
+}
+
+impl<'a, K, V, I: Iterator<Item = (K, V)>> Iterator for ForwardedParams<'a, I> {
+    type Item = (ForwardedItem<'a, K>, ForwardedItem<'a, V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((key, value)) = self.inner.next() {
+            return Some((ForwardedItem::Inner(key), ForwardedItem::Inner(value)));
+        }
+        self.unknown.next().map(|(key, value)| {
+            (
+                ForwardedItem::Unknown(Cow::Borrowed(key.as_str())),
+                ForwardedItem::Unknown(value.decode_utf8_lossy()),
+            )
+        })
+    }
+}
+
+/// Key or value produced by [`ForwardedParams`].
+#[derive(Debug, Clone)]
+pub enum ForwardedItem<'a, T> {
+    /// Produced by the inner extras.
+    Inner(T),
+    /// Decoded key or value of an unknown parameter.
+    Unknown(Cow<'a, str>),
+}
+
+impl<'a, T: fmt::Display> fmt::Display for ForwardedItem<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForwardedItem::Inner(item) => fmt::Display::fmt(item, f),
+            ForwardedItem::Unknown(item) => f.write_str(item),
+        }
+    }
+}
+
+/// Serializes the parameters of the contained variant.
+#[cfg(feature = "either")]
+#[cfg_attr(docsrs, doc(cfg(feature = "either")))]
+impl<A: SerializeParams, B: SerializeParams> SerializeParams for either::Either<A, B> {
+    type Key = either::Either<A::Key, B::Key>;
+    type Value = either::Either<A::Value, B::Value>;
+    #[allow(clippy::type_complexity)]
+    type Iterator = either::Either<
+        core::iter::Map<A::Iterator, fn((A::Key, A::Value)) -> (Self::Key, Self::Value)>,
+        core::iter::Map<B::Iterator, fn((B::Key, B::Value)) -> (Self::Key, Self::Value)>,
+    >;
+
+    fn serialize_params(self) -> Self::Iterator {
+        match self {
+            either::Either::Left(left) => either::Either::Left(
+                left.serialize_params()
+                    .map(|(key, value)| (either::Either::Left(key), either::Either::Left(value))),
+            ),
+            either::Either::Right(right) => either::Either::Right(
+                right
+                    .serialize_params()
+                    .map(|(key, value)| (either::Either::Right(key), either::Either::Right(value))),
+            ),
+        }
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            either::Either::Left(left) => left.param_count_hint(),
+            either::Either::Right(right) => right.param_count_hint(),
+        }
+    }
+}
+
+/// Serializes the parameters of the contained variant.
+#[cfg(feature = "either")]
+#[cfg_attr(docsrs, doc(cfg(feature = "either")))]
+impl<'a, A, B> SerializeParams for &'a either::Either<A, B>
+where
+    &'a A: SerializeParams,
+    &'a B: SerializeParams,
+{
+    type Key = <either::Either<&'a A, &'a B> as SerializeParams>::Key;
+    type Value = <either::Either<&'a A, &'a B> as SerializeParams>::Value;
+    type Iterator = <either::Either<&'a A, &'a B> as SerializeParams>::Iterator;
+
+    fn serialize_params(self) -> Self::Iterator {
+        self.as_ref().serialize_params()
+    }
+
+    fn param_count_hint(&self) -> (usize, Option<usize>) {
+        let inner: either::Either<&'a A, &'a B> = self.as_ref();
+        inner.param_count_hint()
+    }
+}
+
+/// Returns `true` if the character is not allowed in parameter keys.
+fn is_disallowed_in_key(c: char) -> bool {
+    matches!(c, '=' | '&' | '?' | '#')
+}
+
+/// Returns `true` if the character is percent-encoded when writing parameter keys.
+fn needs_encoding_in_key(c: char) -> bool {
+    !c.is_ascii() || c.is_ascii_control() || c == ' '
+}
+
+/// Returns the first character not allowed in keys along with its byte position.
+fn find_disallowed_in_key(key: &str) -> Option<(usize, char)> {
+    key.char_indices().find(|(_, c)| is_disallowed_in_key(*c))
+}
+
+/// Checks whether `key` can be used as a parameter key.
+///
+/// Keys must not be empty and must not contain any of `=`, `&`, `?` and `#` because these would
+/// break the structure of the URI. Non-ASCII characters, control characters and space are allowed
+/// but they are percent-encoded when displayed.
+pub fn validate_param_key(key: &str) -> Result<(), InvalidKeyError> {
+    if key.is_empty() {
+        return Err(InvalidKeyError(InvalidKeyErrorInner::Empty));
+    }
+    match find_disallowed_in_key(key) {
+        Some((position, character)) => Err(InvalidKeyError(InvalidKeyErrorInner::DisallowedCharacter { character, position })),
+        None => Ok(()),
+    }
+}
+
+/// Error returned when a parameter key is empty or contains a disallowed character.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidKeyError(InvalidKeyErrorInner);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum InvalidKeyErrorInner {
+    Empty,
+    DisallowedCharacter { character: char, position: usize },
+}
+
+impl InvalidKeyError {
+    /// Returns the character that is not allowed.
+    ///
+    /// Returns `None` if the key is empty.
+    pub fn character(&self) -> Option<char> {
+        match self.0 {
+            InvalidKeyErrorInner::Empty => None,
+            InvalidKeyErrorInner::DisallowedCharacter { character, .. } => Some(character),
         }
-        self.0.write_char(c)
+    }
+
+    /// Returns the byte position of the character within the key.
+    ///
+    /// Returns `None` if the key is empty.
+    pub fn position(&self) -> Option<usize> {
+        match self.0 {
+            InvalidKeyErrorInner::Empty => None,
+            InvalidKeyErrorInner::DisallowedCharacter { position, .. } => Some(position),
+        }
+    }
+}
+
+impl fmt::Display for InvalidKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            InvalidKeyErrorInner::Empty => write!(f, "the parameter key is empty"),
+            InvalidKeyErrorInner::DisallowedCharacter { character, position } => {
+                write!(
+                    f,
+                    "the parameter key contains disallowed character {:?} at position {}",
+                    character, position
+                )
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for InvalidKeyError {}
+
+/// Parameter key that was checked to not contain disallowed characters.
+///
+/// Using this as [`SerializeParams::Key`] guarantees displaying [`Uri`] will not panic because of
+/// invalid keys.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ValidParamKey(String);
+
+impl ValidParamKey {
+    /// Returns the key as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the inner `String`.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl TryFrom<String> for ValidParamKey {
+    type Error = InvalidKeyError;
+
+    fn try_from(key: String) -> Result<Self, Self::Error> {
+        validate_param_key(&key)?;
+        Ok(ValidParamKey(key))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ValidParamKey {
+    type Error = InvalidKeyError;
+
+    fn try_from(key: &'a str) -> Result<Self, Self::Error> {
+        validate_param_key(key)?;
+        Ok(ValidParamKey(key.to_owned()))
+    }
+}
+
+impl core::str::FromStr for ValidParamKey {
+    type Err = InvalidKeyError;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        key.try_into()
+    }
+}
+
+impl AsRef<str> for ValidParamKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ValidParamKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// What to do when a key is invalid.
+#[derive(Copy, Clone)]
+enum InvalidKeyAction {
+    /// Panic with a descriptive message, used by `Display` which can't report errors.
+    Panic,
+    /// Return `fmt::Error`.
+    Fail,
+}
+
+impl InvalidKeyAction {
+    fn handle(self, key: &dyn fmt::Display, error: InvalidKeyError) -> fmt::Error {
+        match self {
+            InvalidKeyAction::Panic => match error.0 {
+                InvalidKeyErrorInner::Empty => panic!("parameter key is empty"),
+                InvalidKeyErrorInner::DisallowedCharacter { character, position } => {
+                    panic!("key '{}' contains disallowed character {:?} at position {}", key, character, position)
+                },
+            },
+            InvalidKeyAction::Fail => fmt::Error,
+        }
+    }
+}
+
+/// Checks if the display implementation outputs a character disallowed in keys.
+struct KeyChecker<'a, W: fmt::Write> {
+    writer: W,
+    key: &'a dyn fmt::Display,
+    position: usize,
+    on_invalid: InvalidKeyAction,
+}
+
+impl<'a, W: fmt::Write> fmt::Write for KeyChecker<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if let Some((position, character)) = find_disallowed_in_key(s) {
+            let position = self.position + position;
+            let error = InvalidKeyError(InvalidKeyErrorInner::DisallowedCharacter { character, position });
+            return Err(self.on_invalid.handle(self.key, error));
+        }
+        self.position += s.len();
+        let mut start = 0;
+        for (position, character) in s.char_indices().filter(|(_, c)| needs_encoding_in_key(*c)) {
+            self.writer.write_str(&s[start..position])?;
+            let end = position + character.len_utf8();
+            for byte in s[position..end].bytes() {
+                self.writer.write_str(percent_encoding_rfc3986::percent_encode_byte(byte))?;
+            }
+            start = end;
+        }
+        self.writer.write_str(&s[start..])
     }
 }
 
 /// Set of characters that will be percent-encoded
-const ASCII_SET: percent_encoding_rfc3986::AsciiSet = percent_encoding_rfc3986::CONTROLS.add(b'&').add(b'?').add(b' ').add(b'=');
+const ASCII_SET: AsciiSet = percent_encoding_rfc3986::CONTROLS.add(b'&').add(b'?').add(b' ').add(b'=');
 
 /// Percent-encodes writes.
 struct WriterEncoder<W: fmt::Write>(W);
@@ -69,6 +661,128 @@ impl<T: fmt::Display> fmt::Display for DisplayEncoder<T> {
     }
 }
 
+impl<'a> Param<'a> {
+    /// Writes the percent-encoded representation of the parameter into `writer`.
+    ///
+    /// The output is the same as the one used when displaying [`Uri`] and doesn't allocate.
+    /// Note that parsed parameters are re-encoded so the output may differ from the original
+    /// input - e.g. needlessly encoded characters get decoded.
+    pub fn write_encoded_to<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.write_encoded_with(writer, &ASCII_SET)
+    }
+
+    /// Writes the decoded value into `writer` without allocating.
+    ///
+    /// Percent-encoded parameters are decoded and validated in small chunks on the stack.
+    /// Returns `fmt::Error` if the decoded value is not valid UTF-8, in which case the valid
+    /// prefix may have already been written.
+    pub fn write_decoded_to<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        // Bytes of a character split across chunks.
+        let mut carry = [0u8; 4];
+        let mut carry_len = 0;
+        self.try_for_each_decoded_chunk(|mut chunk| {
+            if carry_len > 0 {
+                let char_len = match carry[0] {
+                    0xC2..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    _ => 4,
+                };
+                let missing = (char_len - carry_len).min(chunk.len());
+                carry[carry_len..(carry_len + missing)].copy_from_slice(&chunk[..missing]);
+                carry_len += missing;
+                chunk = &chunk[missing..];
+                if carry_len < char_len {
+                    return Ok(());
+                }
+                writer.write_str(core::str::from_utf8(&carry[..char_len]).map_err(|_| fmt::Error)?)?;
+                carry_len = 0;
+            }
+            match core::str::from_utf8(chunk) {
+                Ok(string) => writer.write_str(string),
+                Err(error) => {
+                    let (valid, rest) = chunk.split_at(error.valid_up_to());
+                    // `valid_up_to` guarantees the prefix is valid so this never fails.
+                    writer.write_str(core::str::from_utf8(valid).map_err(|_| fmt::Error)?)?;
+                    if error.error_len().is_some() {
+                        return Err(fmt::Error);
+                    }
+                    // The chunk ends in the middle of a character which is at most 3 bytes here.
+                    carry[..rest.len()].copy_from_slice(rest);
+                    carry_len = rest.len();
+                    Ok(())
+                },
+            }
+        })?;
+        if carry_len > 0 {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+
+    /// Writes the decoded bytes into the `io::Write` without allocating.
+    ///
+    /// Unlike [`write_decoded_to`](Self::write_decoded_to) this doesn't check that the decoded
+    /// value is valid UTF-8. Percent-encoded parameters are decoded in small chunks on the stack.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn try_decode_into_writer<W: std::io::Write + ?Sized>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.try_for_each_decoded_chunk(|chunk| writer.write_all(chunk))
+    }
+
+    /// Encodes the decoded value using the given set of characters.
+    fn write_encoded_with<W: fmt::Write + ?Sized>(&self, writer: &mut W, set: &'static AsciiSet) -> fmt::Result {
+        match &self.0 {
+            // Each decoded byte is encoded separately, which produces the same output as encoding
+            // the whole slice because percent-encoding is byte-oriented.
+            ParamInner::EncodedBorrowed(decoder, _) => decoder
+                .clone()
+                .try_for_each(|byte| write!(writer, "{}", percent_encoding_rfc3986::percent_encode(&[byte], set))),
+            ParamInner::UnencodedBytes(bytes) => write!(writer, "{}", percent_encoding_rfc3986::percent_encode(bytes, set)),
+            ParamInner::UnencodedString(string) => write!(writer, "{}", percent_encoding_rfc3986::utf8_percent_encode(string, set)),
+        }
+    }
+
+    /// Decodes the parameter and encodes it again using a custom set of characters.
+    ///
+    /// Characters present in `set` are percent-encoded using uppercase hex digits. This is useful
+    /// when relaying the parameter to a system with different encoding requirements. See
+    /// [`CONTROLS`](crate::CONTROLS) for a base set.
+    ///
+    /// This returns `String` rather than `Param` because displaying a `Param` always encodes it
+    /// using the standard BIP21 set - use [`into_static`](Param::into_static) to get a parameter
+    /// that is displayed in the canonical form regardless of how it was encoded in the input.
+    pub fn re_encode(&self, set: &'static AsciiSet) -> String {
+        let mut result = String::new();
+        self.write_encoded_with(&mut result, set).expect("writing to string never fails");
+        result
+    }
+
+    /// Returns the percent-encoded representation of the parameter.
+    ///
+    /// If the parameter was parsed the original encoded string is returned as-is, otherwise it's
+    /// encoded the same way as when displaying [`Uri`].
+    pub fn into_encoded_string(self) -> String {
+        match self.0 {
+            ParamInner::EncodedBorrowed(_, encoded) => encoded.to_owned(),
+            ParamInner::UnencodedBytes(bytes) => percent_encoding_rfc3986::percent_encode(&bytes, &ASCII_SET).to_string(),
+            ParamInner::UnencodedString(string) => percent_encoding_rfc3986::utf8_percent_encode(&string, &ASCII_SET).to_string(),
+        }
+    }
+
+    /// Returns the percent-encoded representation of the parameter, borrowing it if possible.
+    ///
+    /// This is the borrowing version of [`into_encoded_string`](Self::into_encoded_string): if
+    /// the parameter was parsed the original encoded string is returned without allocating,
+    /// otherwise the parameter is encoded into a new string.
+    pub fn as_encoded_str_or_encode(&self) -> Cow<'_, str> {
+        match &self.0 {
+            ParamInner::EncodedBorrowed(_, encoded) => Cow::Borrowed(encoded),
+            ParamInner::UnencodedBytes(bytes) => percent_encoding_rfc3986::percent_encode(bytes, &ASCII_SET).to_string().into(),
+            ParamInner::UnencodedString(string) => percent_encoding_rfc3986::utf8_percent_encode(string, &ASCII_SET).to_string().into(),
+        }
+    }
+}
+
 /// Displays [`Param`] as encoded
 ///
 /// This is private because people should generally only display values as decoded
@@ -76,29 +790,37 @@ struct DisplayParam<'a>(&'a Param<'a>);
 
 impl<'a> fmt::Display for DisplayParam<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match &(self.0).0 {
-            // TODO: improve percent_encoding_rfc_3986 so that allocation can be avoided
-            ParamInner::EncodedBorrowed(decoder) => {
-                let decoded = <Cow<'_, [u8]>>::from(decoder.clone());
-                write!(f, "{}", percent_encoding_rfc3986::percent_encode(&decoded, &ASCII_SET))
-            },
-            ParamInner::UnencodedBytes(bytes) => write!(f, "{}", percent_encoding_rfc3986::percent_encode(bytes, &ASCII_SET)),
-            ParamInner::UnencodedString(string) => write!(f, "{}", percent_encoding_rfc3986::utf8_percent_encode(string, &ASCII_SET)),
-        }
+        self.0.write_encoded_to(f)
     }
 }
 
 /// Writes key-value pair with all required symbols around them.
 ///
 /// `value` is **not** percent-encoded - this must be done from the caller.
-fn write_param(writer: &mut impl fmt::Write, key: impl fmt::Display, value: impl fmt::Display, no_params: &mut bool) -> fmt::Result {
+fn write_param(
+    writer: &mut impl fmt::Write,
+    key: impl fmt::Display,
+    value: impl fmt::Display,
+    no_params: &mut bool,
+    on_invalid: InvalidKeyAction,
+) -> fmt::Result {
     use core::fmt::Write;
 
     if *no_params {
-        write!(EqSignChecker(&mut *writer, &key), "?{}", key)?;
+        writer.write_char('?')?;
         *no_params = false;
     } else {
-        write!(EqSignChecker(&mut *writer, &key), "&{}", key)?;
+        writer.write_char('&')?;
+    }
+    let mut checker = KeyChecker {
+        writer: &mut *writer,
+        key: &key,
+        position: 0,
+        on_invalid,
+    };
+    write!(checker, "{}", key)?;
+    if checker.position == 0 {
+        return Err(on_invalid.handle(&key, InvalidKeyError(InvalidKeyErrorInner::Empty)));
     }
     write!(writer, "={}", value)
 }
@@ -106,7 +828,7 @@ fn write_param(writer: &mut impl fmt::Write, key: impl fmt::Display, value: impl
 /// Write key-value pair if `value` is `Some`.
 fn maybe_write_param(writer: &mut impl fmt::Write, key: impl fmt::Display, value: Option<&Param<'_>>, no_params: &mut bool) -> fmt::Result {
     match value {
-        Some(value) => write_param(writer, key, DisplayParam(value), no_params),
+        Some(value) => write_param(writer, key, DisplayParam(value), no_params, InvalidKeyAction::Fail),
         None => Ok(()),
     }
 }
@@ -114,30 +836,448 @@ fn maybe_write_param(writer: &mut impl fmt::Write, key: impl fmt::Display, value
 /// Write key-value pair if `value` is `Some`.
 fn maybe_display_param(writer: &mut impl fmt::Write, key: impl fmt::Display, value: Option<impl fmt::Display>, no_params: &mut bool) -> fmt::Result {
     match value {
-        Some(value) => write_param(writer, key, DisplayEncoder(value), no_params),
+        Some(value) => write_param(writer, key, DisplayEncoder(value), no_params, InvalidKeyAction::Fail),
         None => Ok(()),
     }
 }
 
 /// Formats QR-code-optimized URI if alternate form (`{:#}`) is used.
-#[rustfmt::skip]
-impl<'a, T> fmt::Display for Uri<'a, bitcoin::address::NetworkChecked, T> where for<'b> &'b T: SerializeParams {
+///
+/// See also [`Uri::display_as`] which makes the choice explicit.
+///
+/// ```
+/// let uri: bip21::Uri<'_, _> = bip21::Uri::parse("bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?label=Luke-Jr").unwrap();
+/// let uri = uri.assume_checked();
+/// assert_eq!(format!("{}", uri), "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?label=Luke-Jr");
+/// assert_eq!(format!("{:#}", uri), "bitcoin:BC1QAR0SRRR7XFKVY5L643LYDNW9RE59GTZZWF5MDQ?label=Luke-Jr");
+/// ```
+impl<'a, T> fmt::Display for Uri<'a, bitcoin::address::NetworkChecked, T>
+where
+    for<'b> &'b T: SerializeParams,
+{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if f.alternate() {
-            write!(f, "bitcoin:{:#}", self.address)?;
+        let mode = if f.alternate() { UriDisplayMode::Qr } else { UriDisplayMode::Standard };
+        self.write_with(f, mode, InvalidKeyAction::Panic)
+    }
+}
+
+/// Formats the URI the same way as `Display`.
+///
+/// This is only implemented for [`NoExtras`](crate::NoExtras) because a generic impl makes
+/// `String::from` calls with a not-yet-inferred argument overflow the trait solver.
+impl<'a> From<Uri<'a, bitcoin::address::NetworkChecked>> for String {
+    #[inline]
+    fn from(value: Uri<'a, bitcoin::address::NetworkChecked>) -> Self {
+        value.to_string()
+    }
+}
+
+/// Formats the URI the same way as `Display`.
+impl<'a, 'u> From<&'u Uri<'a, bitcoin::address::NetworkChecked>> for String {
+    #[inline]
+    fn from(value: &'u Uri<'a, bitcoin::address::NetworkChecked>) -> Self {
+        value.to_string()
+    }
+}
+
+/// Determines how [`Uri`] is formatted by [`Uri::display_as`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum UriDisplayMode {
+    /// Optimized for QR codes - the address is uppercase if it's bech32.
+    ///
+    /// This is the same as the alternate form (`{:#}`).
+    Qr,
+
+    /// The address is formatted in its usual form.
+    ///
+    /// This is the same as the normal form (`{}`).
+    Standard,
+}
+
+/// Displays [`Uri`] in the selected mode.
+struct DisplayUri<'u, 'a, T> {
+    uri: &'u Uri<'a, bitcoin::address::NetworkChecked, T>,
+    mode: UriDisplayMode,
+}
+
+impl<'u, 'a, T> fmt::Display for DisplayUri<'u, 'a, T>
+where
+    for<'b> &'b T: SerializeParams,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.uri.write_with(f, self.mode, InvalidKeyAction::Panic)
+    }
+}
+
+/// Number of data codewords of QR code versions 1 to 40 at error correction level M.
+const QR_CAPACITY_M: [usize; 40] = [
+    16, 28, 44, 64, 86, 108, 124, 154, 182, 216, 254, 290, 334, 365, 415, 453, 507, 563, 627, 669, 714, 782, 860, 914, 1000, 1062, 1128, 1193, 1267,
+    1373, 1455, 1541, 1631, 1725, 1812, 1914, 1992, 2102, 2216, 2334,
+];
+
+/// Returns `true` if the byte can be encoded in the alphanumeric mode of QR codes.
+fn is_qr_alphanumeric(byte: u8) -> bool {
+    matches!(byte, b'0'..=b'9' | b'A'..=b'Z' | b' ' | b'$' | b'%' | b'*' | b'+' | b'-' | b'.' | b'/' | b':')
+}
+
+/// Approximates the number of bits needed to encode `s` in a QR code of given version.
+///
+/// Runs of alphanumeric characters get their own segment if it's shorter than encoding them as
+/// bytes, the other characters are encoded in byte segments.
+fn qr_data_bits(s: &str, version: u8) -> usize {
+    const MODE_BITS: usize = 4;
+    let (alphanumeric_count_bits, byte_count_bits) = match version {
+        1..=9 => (9, 8),
+        10..=26 => (11, 16),
+        _ => (13, 16),
+    };
+
+    let bytes = s.as_bytes();
+    let mut bits = 0;
+    let mut byte_segment_len = 0;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let is_alphanumeric = is_qr_alphanumeric(bytes[pos]);
+        let run_len = bytes[pos..]
+            .iter()
+            .take_while(|byte| is_qr_alphanumeric(**byte) == is_alphanumeric)
+            .count();
+        pos += run_len;
+        let alphanumeric_bits = MODE_BITS + alphanumeric_count_bits + run_len / 2 * 11 + run_len % 2 * 6;
+        if is_alphanumeric && alphanumeric_bits < run_len * 8 {
+            bits += alphanumeric_bits;
         } else {
-            write!(f, "bitcoin:{}", self.address)?;
+            byte_segment_len += run_len;
+            continue;
+        }
+        if byte_segment_len > 0 {
+            bits += MODE_BITS + byte_count_bits + byte_segment_len * 8;
+            byte_segment_len = 0;
+        }
+    }
+    if byte_segment_len > 0 {
+        bits += MODE_BITS + byte_count_bits + byte_segment_len * 8;
+    }
+    bits
+}
+
+/// URI serialized in advance.
+///
+/// This is returned from [`Uri::precompute`] and it's useful when the same URI is displayed many
+/// times, e.g. by a server generating QR codes. Accessing the string is cheap since it's already
+/// formatted.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct UriStr(String);
+
+impl UriStr {
+    /// Returns the serialized URI.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the serialized URI as an owned `String`.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl core::ops::Deref for UriStr {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for UriStr {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl core::borrow::Borrow<str> for UriStr {
+    #[inline]
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for UriStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<UriStr> for String {
+    #[inline]
+    fn from(value: UriStr) -> Self {
+        value.0
+    }
+}
+
+/// Forwards writes to `io::Write`, storing the error.
+#[cfg(feature = "std")]
+struct IoWriter<'a, W: std::io::Write + ?Sized> {
+    writer: &'a mut W,
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, W: std::io::Write + ?Sized> fmt::Write for IoWriter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+/// Appends written strings to a byte vector.
+struct BytesWriter<'a>(&'a mut Vec<u8>);
+
+impl<'a> fmt::Write for BytesWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl<'a, T> Uri<'a, bitcoin::address::NetworkChecked, T>
+where
+    for<'b> &'b T: SerializeParams,
+{
+    /// Returns a value displaying the URI in the given mode.
+    ///
+    /// This is a more discoverable alternative to choosing between `{}` and `{:#}`.
+    pub fn display_as(&self, mode: UriDisplayMode) -> impl fmt::Display + '_ {
+        DisplayUri { uri: self, mode }
+    }
+
+    /// Serializes the URI in advance so that it can be accessed cheaply many times.
+    ///
+    /// The output is the same as with `{}`.
+    pub fn precompute(&self) -> UriStr {
+        UriStr(self.to_string())
+    }
+
+    /// Estimates the number of modules of a QR code encoding this URI.
+    ///
+    /// The URI is formatted the same way as with `{:#}` and split into segments of characters
+    /// encodable in the alphanumeric mode (e.g. the uppercase bech32 address) and the rest which
+    /// is encoded in the byte mode. The smallest QR code version with enough capacity at error
+    /// correction level M is then found and its total number of modules (width squared) is
+    /// returned.
+    ///
+    /// This is an approximation - a real encoder may choose segments more cleverly and different
+    /// error correction level so the version can differ by one or two. It's intended for
+    /// deciding whether the QR code is going to be too dense, e.g. to fall back to encoding just
+    /// the address. Returns `usize::MAX` if the URI doesn't fit into the largest QR code.
+    pub fn estimate_qr_complexity(&self) -> usize {
+        let uri = alloc::format!("{:#}", self);
+        (1u8..=40)
+            .find(|&version| qr_data_bits(&uri, version) <= QR_CAPACITY_M[usize::from(version - 1)] * 8)
+            .map_or(usize::MAX, |version| {
+                let width = 17 + 4 * usize::from(version);
+                width * width
+            })
+    }
+
+    /// Serializes the URI into an arbitrary `fmt::Write` in the standard form.
+    ///
+    /// This is useful with fixed-capacity strings on embedded systems as it doesn't allocate
+    /// (unless the extras do). The output is the same as with `{}`.
+    ///
+    /// Unlike `Display` this doesn't panic if a key of the extras is invalid (see
+    /// [`validate_param_key`]), it returns `fmt::Error` instead. Part of the URI may already be
+    /// written in that case.
+    pub fn serialize_to<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.write_with(&mut &mut *writer, UriDisplayMode::Standard, InvalidKeyAction::Fail)
+    }
+
+    /// Serializes the URI into an arbitrary `fmt::Write` optimized for QR codes.
+    ///
+    /// The output is the same as with `{:#}`, see [`UriDisplayMode::Qr`]. Invalid keys are
+    /// handled the same way as in [`serialize_to`](Self::serialize_to).
+    pub fn serialize_to_qr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.write_with(&mut &mut *writer, UriDisplayMode::Qr, InvalidKeyAction::Fail)
+    }
+
+    /// Writes the URI in the given mode, this is the implementation of all formatting methods.
+    fn write_with(&self, writer: &mut impl fmt::Write, mode: UriDisplayMode, on_invalid: InvalidKeyAction) -> fmt::Result {
+        match mode {
+            UriDisplayMode::Qr => write!(writer, "{}:{:#}", crate::SCHEME, self.address)?,
+            UriDisplayMode::Standard => write!(writer, "{}:{}", crate::SCHEME, self.address)?,
         }
         let mut no_params = true;
         let display_amount = self.amount.as_ref().map(|amount| amount.display_in(Denomination::Bitcoin));
 
-        maybe_display_param(f, "amount", display_amount, &mut no_params)?;
-        maybe_write_param(f, "label", self.label.as_ref(), &mut no_params)?;
-        maybe_write_param(f, "message", self.message.as_ref(), &mut no_params)?;
+        maybe_display_param(writer, "amount", display_amount, &mut no_params)?;
+        maybe_write_param(writer, "label", self.label.as_ref(), &mut no_params)?;
+        maybe_write_param(writer, "message", self.message.as_ref(), &mut no_params)?;
 
         for (key, value) in self.extras.serialize_params() {
-            write_param(f, key, DisplayEncoder(value), &mut no_params)?;
+            write_param(writer, key, DisplayEncoder(value), &mut no_params, on_invalid)?;
         }
         Ok(())
     }
+
+    /// Writes the URI into the `io::Write` without allocating an intermediate `String`.
+    ///
+    /// The output is the same as with `{}`. Like [`serialize_to`](Self::serialize_to) this
+    /// doesn't panic if a key of the extras is invalid, it returns an error of kind
+    /// `InvalidData` instead. Part of the URI may already be written in that case.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn format_to_bytes<W: std::io::Write + ?Sized>(&self, writer: &mut W) -> std::io::Result<()> {
+        let mut adapter = IoWriter { writer, error: None };
+        self.write_with(&mut adapter, UriDisplayMode::Standard, InvalidKeyAction::Fail)
+            .map_err(|_| {
+                adapter
+                    .error
+                    .unwrap_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "failed to format the URI"))
+            })
+    }
+
+    /// Serializes the URI into a byte vector.
+    ///
+    /// This is equivalent to `uri.to_string().into_bytes()`.
+    ///
+    /// **Panics** if the `Display` implementation of extras returns an error or a key of the
+    /// extras is invalid (see [`validate_param_key`]).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write_to_bytes(&mut buf);
+        buf
+    }
+
+    /// Appends the serialized URI to an existing buffer.
+    ///
+    /// This can be used to avoid allocations when serializing multiple URIs.
+    ///
+    /// **Panics** if the `Display` implementation of extras returns an error or a key of the
+    /// extras is invalid (see [`validate_param_key`]).
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>) {
+        use fmt::Write;
+
+        write!(BytesWriter(buf), "{}", self).expect("a Display implementation returned an error unexpectedly");
+    }
+}
+
+impl<'a, NetVal: bitcoin::address::NetworkValidation, T> Uri<'a, NetVal, T> {
+    /// Returns an iterator over all parameters present in the URI.
+    ///
+    /// The parameters are yielded in the same order as when displaying the URI: `amount`, `label`,
+    /// `message` and then the extras. The amount is formatted in bitcoins and the keys and values
+    /// of extras are formatted using their `Display` implementations. Other parameters are
+    /// borrowed without copying.
+    pub fn params<'b>(&'b self) -> UriParamIter<'b, T>
+    where
+        &'b T: SerializeParams,
+    {
+        UriParamIter::<'b, T> {
+            amount: self.amount.map(|amount| amount.display_in(Denomination::Bitcoin).to_string().into()),
+            label: self.label.as_ref().map(Param::reborrow),
+            message: self.message.as_ref().map(Param::reborrow),
+            extras: <&'b T as SerializeParams>::serialize_params(&self.extras),
+        }
+    }
+
+    /// Returns `true` if the extras serialize at least one parameter.
+    ///
+    /// The [`param_count_hint`](SerializeParams::param_count_hint) is consulted first so the
+    /// iterator is only created if the hint is inconclusive. [`NoExtras`](crate::NoExtras) always
+    /// returns `false`.
+    pub fn has_any_extras(&self) -> bool
+    where
+        for<'b> &'b T: SerializeParams,
+    {
+        match (&self.extras).param_count_hint() {
+            (0, Some(0)) => false,
+            (0, _) => (&self.extras).serialize_params().next().is_some(),
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if both URIs have the same fields and serialize the same extra parameters.
+    ///
+    /// Unlike `==` this works with different types of extras. The extras are compared as sets of
+    /// serialized key-value pairs so their order doesn't matter. A URI with extras that happen to
+    /// serialize no parameters is equal to the same URI with [`NoExtras`](crate::NoExtras).
+    ///
+    /// This allocates, prefer `==` if the extras are of the same type and implement `PartialEq`.
+    pub fn params_equal<U>(&self, other: &Uri<'_, NetVal, U>) -> bool
+    where
+        T: SerializeParamsRef,
+        U: SerializeParamsRef,
+    {
+        if self.diff(other).has_changes() {
+            return false;
+        }
+        let mut params = self.extras.params_to_vec();
+        let mut other_params = other.extras.params_to_vec();
+        params.sort_unstable();
+        other_params.sort_unstable();
+        params == other_params
+    }
+}
+
+/// Iterator over the parameters of [`Uri`].
+///
+/// This is returned from [`Uri::params`].
+pub struct UriParamIter<'a, T>
+where
+    &'a T: SerializeParams,
+{
+    amount: Option<Param<'static>>,
+    label: Option<Param<'a>>,
+    message: Option<Param<'a>>,
+    extras: <&'a T as SerializeParams>::Iterator,
+}
+
+impl<'a, T> UriParamIter<'a, T>
+where
+    &'a T: SerializeParams,
+{
+    fn known_count(&self) -> usize {
+        self.amount.is_some() as usize + self.label.is_some() as usize + self.message.is_some() as usize
+    }
+}
+
+impl<'a, T> Iterator for UriParamIter<'a, T>
+where
+    &'a T: SerializeParams,
+{
+    type Item = (Cow<'a, str>, Param<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(amount) = self.amount.take() {
+            return Some((Cow::Borrowed("amount"), amount));
+        }
+        if let Some(label) = self.label.take() {
+            return Some((Cow::Borrowed("label"), label));
+        }
+        if let Some(message) = self.message.take() {
+            return Some((Cow::Borrowed("message"), message));
+        }
+        self.extras
+            .next()
+            .map(|(key, value)| (Cow::Owned(key.to_string()), value.to_string().into()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let known_count = self.known_count();
+        let (min, max) = self.extras.size_hint();
+        (min.saturating_add(known_count), max.and_then(|max| max.checked_add(known_count)))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for UriParamIter<'a, T>
+where
+    &'a T: SerializeParams,
+    <&'a T as SerializeParams>::Iterator: ExactSizeIterator,
+{
 }